@@ -1,21 +1,82 @@
+mod backups;
+mod compare;
 mod download;
+mod history;
 mod list;
+mod manifest;
+mod queue;
 mod remotes;
+mod restore;
+mod scheduler;
+mod search;
+mod tuning;
 mod types;
 mod utils;
+mod verify;
 
 // Types
+pub use backups::{BackupInfo, RetentionPolicy};
+pub use compare::{ArchiveComparison, ArchiveEntry, ArchiveStatus, FolderStatus};
+pub use download::{
+    CancelledJobs, DownloadConfig, DownloadStatus, DownloadSummary, FilesystemPaths,
+    build_file_filter,
+};
+pub use history::{
+    FailedFile, FileEvent, FileEventKind, RenamedFile, RunOrigin, SyncRun, SyncRunStatus,
+    SyncRunSummary,
+};
+pub use manifest::Manifest;
+pub use queue::{JobQueue, QueueJob, QueueJobStatus};
 pub use remotes::GdriveAuthState;
-pub use types::GdriveFile;
+pub use restore::{BackupFile, BackupFileStatus, RestoreConflict, RestoreResult};
+pub use scheduler::{ProfileRun, ProfileRunStatus, Schedule, Scheduler, SyncProfile};
+pub use search::{SearchHit, SearchQuery, SearchResults};
+pub use tuning::{BandwidthLimit, TransferOptions};
+pub use types::{FolderEntry, FolderPage, GdriveFile, GdriveListing};
+pub use verify::VerifyResult;
 
 // Command functions
-pub use download::{__cmd__check_dry_run, __cmd__download_gdrive};
-pub use list::__cmd__list_gdrive_files;
+pub use backups::{
+    __cmd__get_backup_retention, __cmd__list_backups, __cmd__prune_backups,
+    __cmd__set_backup_retention,
+};
+pub use compare::__cmd__compare_archive;
+pub use download::{__cmd__cancel_download, __cmd__check_dry_run, __cmd__download_gdrive};
+pub use history::{__cmd__find_file_history, __cmd__get_sync_run, __cmd__list_sync_runs};
+pub use list::{__cmd__list_gdrive_files, __cmd__list_gdrive_folder, __cmd__refresh_gdrive_files};
+pub use queue::{
+    __cmd__cancel_queue_job, __cmd__enqueue_download, __cmd__list_queue_jobs,
+    __cmd__move_queue_job, __cmd__pause_queue_job, __cmd__resume_queue_job, __cmd__retry_queue_job,
+    __cmd__set_queue_concurrency,
+};
 pub use remotes::{
     __cmd__cancel_gdrive_auth, __cmd__create_gdrive_remote, __cmd__get_gdrive_remotes,
 };
+pub use restore::{__cmd__diff_backup, __cmd__restore_backup};
+pub use scheduler::{
+    __cmd__create_sync_profile, __cmd__delete_sync_profile, __cmd__list_sync_profiles,
+    __cmd__run_sync_profile, __cmd__update_sync_profile,
+};
+pub use search::__cmd__search_archive;
+pub use tuning::__cmd__set_bandwidth_limit;
+pub use verify::__cmd__verify_archive;
 
 // Functions
-pub use download::{DryRunResult, check_dry_run, download_gdrive};
-pub use list::list_gdrive_files;
+pub use backups::{get_backup_retention, list_backups, prune_backups, set_backup_retention};
+pub use compare::compare_archive;
+pub use download::{DryRunResult, cancel_download, check_dry_run, download_gdrive};
+pub use history::{find_file_history, get_sync_run, list_sync_runs};
+pub use list::{list_gdrive_files, list_gdrive_folder, refresh_gdrive_files};
+pub use queue::{
+    cancel_queue_job, enqueue_download, list_queue_jobs, move_queue_job, pause_queue_job,
+    resume_queue_job, retry_queue_job, set_queue_concurrency,
+};
 pub use remotes::{cancel_gdrive_auth, create_gdrive_remote, get_gdrive_remotes};
+pub use restore::{diff_backup, restore_backup};
+pub use scheduler::{
+    create_sync_profile, delete_sync_profile, list_sync_profiles, run_sync_profile,
+    update_sync_profile,
+};
+pub use search::search_archive;
+pub use tuning::set_bandwidth_limit;
+pub use verify::verify_archive;
@@ -0,0 +1,518 @@
+use super::download::{self, BACKUP_PREFIX, BACKUP_TIMESTAMP_FORMAT, DownloadConfig};
+use super::queue::JobQueue;
+use crate::error::AppError;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Limits applied when pruning backups. Limits that aren't set keep
+/// everything, so the default policy never removes a backup.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionPolicy {
+    /// Number of most recent backups to keep
+    pub keep_last: Option<usize>,
+    /// Backups older than this many days are removed
+    pub max_age_days: Option<u32>,
+    /// The oldest backups are removed until the rest fit in this many bytes
+    pub max_total_bytes: Option<u64>,
+}
+
+/// A `Backup-KAR-<timestamp>` folder next to the destination
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub name: String,
+    pub path: String,
+    pub created_at: String,
+    pub size: u64,
+    pub file_count: u64,
+    #[serde(skip)]
+    created: NaiveDateTime,
+}
+
+fn get_policy_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| AppError::Internal(format!("Failed to get app data dir: {}", e)))?
+        .join("backup-retention.json"))
+}
+
+async fn load_policy(app: &AppHandle) -> Result<RetentionPolicy, AppError> {
+    let path = get_policy_path(app)?;
+    if !path.exists() {
+        return Ok(RetentionPolicy::default());
+    }
+
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| AppError::io("Failed to read retention policy", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::Internal(format!("Failed to parse retention policy: {}", e)))
+}
+
+/// The folder backups are created in for a destination, one level above
+/// the folder being synced
+fn get_backup_dir(destination: &str, create_subfolder: bool) -> Result<PathBuf, AppError> {
+    download::destination_path(destination, create_subfolder)
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or(AppError::InvalidDestination(
+            "Cannot get parent directory of destination".to_string(),
+        ))
+}
+
+/// The folder of a single backup, rejecting names that aren't backups
+pub(super) fn get_backup_path(
+    destination: &str,
+    create_subfolder: bool,
+    name: &str,
+) -> Result<PathBuf, AppError> {
+    if !name.starts_with(BACKUP_PREFIX) || name.contains(['/', '\\']) {
+        return Err(AppError::InvalidRequest(format!(
+            "{} is not a backup folder",
+            name
+        )));
+    }
+
+    let path = get_backup_dir(destination, create_subfolder)?.join(name);
+    if !path.is_dir() {
+        return Err(AppError::NotFound(format!("Backup {}", name)));
+    }
+    Ok(path)
+}
+
+/// Total size and number of files below a folder, not following links
+fn measure_dir(root: &Path) -> (u64, u64) {
+    let mut size = 0;
+    let mut file_count = 0;
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.path().symlink_metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                size += metadata.len();
+                file_count += 1;
+            }
+        }
+    }
+
+    (size, file_count)
+}
+
+/// List the backups in a folder, newest first. Folders whose name doesn't
+/// carry a backup timestamp are left out.
+pub fn find_backups(dir: &Path) -> Result<Vec<BackupInfo>, AppError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| AppError::io("Failed to read backup folder", e))?;
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| AppError::io("Failed to read backup folder", e))?;
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(timestamp) = name.strip_prefix(BACKUP_PREFIX) else {
+            continue;
+        };
+        let Ok(created) = NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT) else {
+            continue;
+        };
+
+        let (size, file_count) = measure_dir(&path);
+        let created_at = Local
+            .from_local_datetime(&created)
+            .earliest()
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_else(|| created.to_string());
+
+        backups.push(BackupInfo {
+            name,
+            path: path.to_string_lossy().to_string(),
+            created_at,
+            size,
+            file_count,
+            created,
+        });
+    }
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+    Ok(backups)
+}
+
+/// Pick the backups the policy doesn't keep, given backups sorted newest
+/// first. The newest backup is always kept, however old or large it is.
+pub fn select_expired(
+    backups: &[BackupInfo],
+    policy: &RetentionPolicy,
+    now: NaiveDateTime,
+) -> Vec<usize> {
+    let mut total = 0;
+
+    backups
+        .iter()
+        .enumerate()
+        .filter(|(index, backup)| {
+            if *index == 0 {
+                total += backup.size;
+                return false;
+            }
+            let too_many = policy.keep_last.is_some_and(|keep| *index >= keep);
+            let too_old = policy
+                .max_age_days
+                .is_some_and(|days| now - backup.created > chrono::Duration::days(days as i64));
+            if too_many || too_old {
+                return true;
+            }
+            // Only backups that are kept count towards the size limit
+            total += backup.size;
+            policy.max_total_bytes.is_some_and(|max| total > max)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Remove the backups in a folder that the policy doesn't keep, returning
+/// the ones removed. Backups whose folder is one of the `protected` paths
+/// are never removed.
+pub fn prune_dir(
+    dir: &Path,
+    policy: &RetentionPolicy,
+    now: NaiveDateTime,
+    protected: &[String],
+) -> Result<Vec<BackupInfo>, AppError> {
+    let backups = find_backups(dir)?;
+
+    let mut removed = Vec::new();
+    for index in select_expired(&backups, policy, now) {
+        let backup = &backups[index];
+        if protected
+            .iter()
+            .any(|path| Path::new(path).file_name() == Some(OsStr::new(&backup.name)))
+        {
+            continue;
+        }
+        std::fs::remove_dir_all(&backup.path)
+            .map_err(|e| AppError::io(&format!("Failed to remove {}", backup.name), e))?;
+        removed.push(backup.clone());
+    }
+
+    Ok(removed)
+}
+
+/// Prune a folder, leaving alone the `keep` backup and the ones paused or
+/// pending queue jobs will still write to
+async fn prune(
+    app: &AppHandle,
+    dir: PathBuf,
+    keep: Option<String>,
+) -> Result<Vec<BackupInfo>, AppError> {
+    let policy = load_policy(app).await?;
+    if policy == RetentionPolicy::default() {
+        return Ok(Vec::new());
+    }
+
+    let mut protected = JobQueue::backups_in_use(app).await;
+    protected.extend(keep);
+    let removed = tauri::async_runtime::spawn_blocking(move || {
+        prune_dir(&dir, &policy, Local::now().naive_local(), &protected)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Backup pruning failed: {}", e)))??;
+
+    if !removed.is_empty() {
+        let _ = app.emit("backups-pruned", &removed);
+    }
+    Ok(removed)
+}
+
+/// Apply the retention policy next to the destination of a finished sync,
+/// keeping the backup that sync just filled
+pub(super) async fn prune_after_sync(
+    app: &AppHandle,
+    config: &DownloadConfig,
+    backup_path: Option<&str>,
+) {
+    let Some(dir) = config
+        .build_destination_path()
+        .parent()
+        .map(Path::to_path_buf)
+    else {
+        return;
+    };
+    if let Err(e) = prune(app, dir, backup_path.map(str::to_string)).await {
+        crate::error::report_background_error(app, "Failed to prune backups", e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_backup_retention(app: AppHandle) -> Result<RetentionPolicy, AppError> {
+    load_policy(&app).await
+}
+
+#[tauri::command]
+pub async fn set_backup_retention(app: AppHandle, policy: RetentionPolicy) -> Result<(), AppError> {
+    if policy.keep_last == Some(0) {
+        return Err(AppError::InvalidRequest(
+            "At least one backup must be kept".to_string(),
+        ));
+    }
+
+    let path = get_policy_path(&app)?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| AppError::io("Failed to create app data dir", e))?;
+    }
+    let content = serde_json::to_string_pretty(&policy)?;
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|e| AppError::io("Failed to save retention policy", e))
+}
+
+/// List the backups kept for a destination, newest first
+#[tauri::command]
+pub async fn list_backups(
+    destination: String,
+    create_subfolder: bool,
+) -> Result<Vec<BackupInfo>, AppError> {
+    let dir = get_backup_dir(&destination, create_subfolder)?;
+    tauri::async_runtime::spawn_blocking(move || find_backups(&dir))
+        .await
+        .map_err(|e| AppError::Internal(format!("Listing backups failed: {}", e)))?
+}
+
+/// Remove the backups of a destination that the retention policy doesn't
+/// keep, returning the ones removed
+#[tauri::command]
+pub async fn prune_backups(
+    app: AppHandle,
+    destination: String,
+    create_subfolder: bool,
+) -> Result<Vec<BackupInfo>, AppError> {
+    let dir = get_backup_dir(&destination, create_subfolder)?;
+    prune(&app, dir, None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::queue::{QueueJob, QueueJobStatus};
+    use super::*;
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn backup(created: &str, size: u64) -> BackupInfo {
+        BackupInfo {
+            name: String::new(),
+            path: String::new(),
+            created_at: String::new(),
+            size,
+            file_count: 1,
+            created: time(created),
+        }
+    }
+
+    #[test]
+    fn policy_limits_are_combined() {
+        let now = time("2026-03-01 12:00");
+        let backups = vec![
+            backup("2026-02-28 12:00", 40),
+            backup("2026-02-20 12:00", 40),
+            backup("2026-02-10 12:00", 40),
+            backup("2026-01-01 12:00", 40),
+        ];
+
+        let keep_last = RetentionPolicy {
+            keep_last: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(select_expired(&backups, &keep_last, now), vec![2, 3]);
+
+        let max_age = RetentionPolicy {
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(select_expired(&backups, &max_age, now), vec![3]);
+
+        let max_size = RetentionPolicy {
+            max_total_bytes: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(select_expired(&backups, &max_size, now), vec![2, 3]);
+
+        let combined = RetentionPolicy {
+            keep_last: Some(3),
+            max_age_days: Some(15),
+            max_total_bytes: Some(1000),
+        };
+        assert_eq!(select_expired(&backups, &combined, now), vec![2, 3]);
+
+        assert!(select_expired(&backups, &RetentionPolicy::default(), now).is_empty());
+    }
+
+    #[test]
+    fn newest_backup_is_always_kept() {
+        let now = time("2026-03-01 12:00");
+        let policy = RetentionPolicy {
+            max_age_days: Some(1),
+            max_total_bytes: Some(10),
+            ..Default::default()
+        };
+
+        let single = vec![backup("2025-01-01 12:00", 500)];
+        assert!(select_expired(&single, &policy, now).is_empty());
+
+        // The newest backup alone exceeds the size limit, so every older one goes
+        let two = vec![
+            backup("2026-03-01 11:00", 500),
+            backup("2026-03-01 10:00", 5),
+        ];
+        assert_eq!(select_expired(&two, &policy, now), vec![1]);
+    }
+
+    #[test]
+    fn only_expired_backup_folders_are_removed() {
+        let dir = std::env::temp_dir().join(format!("backups-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for name in [
+            "Backup-KAR-20260101_090000",
+            "Backup-KAR-20260201_090000",
+            "Backup-KAR-20260301_090000",
+            "Backup-KAR-notes",
+            "An-Unofficial-Neuro-Karaoke-Archive",
+        ] {
+            std::fs::create_dir_all(dir.join(name).join("album")).unwrap();
+            std::fs::write(dir.join(name).join("album/song.mp3"), [0; 8]).unwrap();
+        }
+
+        let backups = find_backups(&dir).unwrap();
+        let names: Vec<&str> = backups.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Backup-KAR-20260301_090000",
+                "Backup-KAR-20260201_090000",
+                "Backup-KAR-20260101_090000",
+            ]
+        );
+        assert_eq!((backups[0].size, backups[0].file_count), (8, 1));
+
+        let policy = RetentionPolicy {
+            keep_last: Some(1),
+            ..Default::default()
+        };
+        let removed = prune_dir(
+            &dir,
+            &policy,
+            time("2026-03-02 00:00"),
+            &[dir
+                .join("Backup-KAR-20260201_090000")
+                .to_string_lossy()
+                .to_string()],
+        )
+        .unwrap();
+        assert_eq!(removed.len(), 1);
+
+        assert!(dir.join("Backup-KAR-20260301_090000").exists());
+        // Kept as the backup of the sync that just ran
+        assert!(dir.join("Backup-KAR-20260201_090000").exists());
+        assert!(!dir.join("Backup-KAR-20260101_090000").exists());
+        assert!(dir.join("Backup-KAR-notes").exists());
+        assert!(dir.join("An-Unofficial-Neuro-Karaoke-Archive").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn queued_job(id: u64, status: QueueJobStatus, backup_path: &Path) -> QueueJob {
+        let mut config = DownloadConfig::new(
+            "https://drive.google.com/drive/folders/1abc".to_string(),
+            backup_path.parent().unwrap().to_string_lossy().to_string(),
+            Some("gdrive".to_string()),
+            true,
+            true,
+            None,
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+        config.keep_backup_path(Some(backup_path.to_string_lossy().to_string()));
+        QueueJob {
+            id,
+            config,
+            status,
+            error: None,
+            rclone_job_id: None,
+            created_at: String::new(),
+            finished_at: None,
+            summary: None,
+            origin: None,
+        }
+    }
+
+    #[test]
+    fn backups_of_unfinished_jobs_are_kept() {
+        let dir = std::env::temp_dir().join(format!("backups-in-use-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for name in [
+            "Backup-KAR-20260101_090000",
+            "Backup-KAR-20260201_090000",
+            "Backup-KAR-20260301_090000",
+        ] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(dir.join(name).join("song.mp3"), [0; 8]).unwrap();
+        }
+
+        // The paused job goes on filling the oldest backup once resumed,
+        // the cancelled one won't touch its backup again
+        let jobs = [
+            queued_job(
+                1,
+                QueueJobStatus::Paused,
+                &dir.join("Backup-KAR-20260101_090000"),
+            ),
+            queued_job(
+                2,
+                QueueJobStatus::Cancelled,
+                &dir.join("Backup-KAR-20260201_090000"),
+            ),
+        ];
+        let protected: Vec<String> = jobs
+            .iter()
+            .filter_map(QueueJob::backup_in_use)
+            .map(str::to_string)
+            .collect();
+
+        let policy = RetentionPolicy {
+            keep_last: Some(1),
+            ..Default::default()
+        };
+        let removed = prune_dir(&dir, &policy, time("2026-03-02 00:00"), &protected).unwrap();
+        let names: Vec<&str> = removed.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["Backup-KAR-20260201_090000"]);
+        assert!(dir.join("Backup-KAR-20260101_090000").exists());
+        assert!(dir.join("Backup-KAR-20260301_090000").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,350 @@
+use super::download;
+use super::manifest;
+use super::types::GdriveFile;
+use crate::api::local::{self, LocalFile};
+use crate::api::rclone;
+use crate::error::AppError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Modification times closer than this count as the same
+const MOD_TIME_TOLERANCE: chrono::Duration = chrono::Duration::seconds(1);
+
+/// How a file in the archive compares to Drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveStatus {
+    UpToDate,
+    /// On Drive, but not downloaded yet
+    MissingLocally,
+    /// Changed on Drive since it was downloaded
+    Outdated,
+    /// Only in the archive, so a sync would delete it
+    LocalOnly,
+    /// Differs from Drive but was changed locally more recently, so a sync
+    /// would overwrite local changes
+    Conflicting,
+}
+
+/// A file on Drive, in the archive, or both
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub status: ArchiveStatus,
+    pub remote_size: Option<i64>,
+    pub local_size: Option<u64>,
+    pub remote_mod_time: Option<String>,
+    pub local_mod_time: Option<String>,
+}
+
+/// Number of files below a folder in each state
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderStatus {
+    pub path: String,
+    pub up_to_date: usize,
+    pub missing_locally: usize,
+    pub outdated: usize,
+    pub local_only: usize,
+    pub conflicting: usize,
+}
+
+impl FolderStatus {
+    fn add(&mut self, status: ArchiveStatus) {
+        match status {
+            ArchiveStatus::UpToDate => self.up_to_date += 1,
+            ArchiveStatus::MissingLocally => self.missing_locally += 1,
+            ArchiveStatus::Outdated => self.outdated += 1,
+            ArchiveStatus::LocalOnly => self.local_only += 1,
+            ArchiveStatus::Conflicting => self.conflicting += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveComparison {
+    pub entries: Vec<ArchiveEntry>,
+    /// Every folder with files, the archive root first as an empty path
+    pub folders: Vec<FolderStatus>,
+    /// When the Drive listing compared against was fetched
+    pub refreshed_at: String,
+}
+
+fn parse_time(time: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time?)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Compare a file on Drive with its local copy
+fn file_status(remote: &GdriveFile, local: &LocalFile, local_md5: Option<&str>) -> ArchiveStatus {
+    let remote_time = parse_time(remote.mod_time.as_deref());
+    let times_match = match (remote_time, local.modified) {
+        (Some(remote), Some(local)) => (remote - local).abs() < MOD_TIME_TOLERANCE,
+        _ => false,
+    };
+    // Google Docs are listed without a size
+    let sizes_match = remote.size < 0 || remote.size as u64 == local.size;
+
+    let same = match (remote.md5(), local_md5) {
+        (Some(remote), Some(local)) => remote.eq_ignore_ascii_case(local),
+        _ => sizes_match && times_match,
+    };
+    if same {
+        return ArchiveStatus::UpToDate;
+    }
+
+    match (remote_time, local.modified) {
+        (Some(remote), Some(local)) if local - remote >= MOD_TIME_TOLERANCE => {
+            ArchiveStatus::Conflicting
+        }
+        _ => ArchiveStatus::Outdated,
+    }
+}
+
+/// Join a Drive listing with a local scan, tagging every file
+fn compare(
+    remote: &[GdriveFile],
+    local: Vec<LocalFile>,
+    local_hashes: &HashMap<String, String>,
+) -> Vec<ArchiveEntry> {
+    let mut local: HashMap<String, LocalFile> = local
+        .into_iter()
+        .map(|file| (file.path.clone(), file))
+        .collect();
+
+    let mut entries: Vec<ArchiveEntry> = remote
+        .iter()
+        .filter(|file| !file.is_dir)
+        .map(|file| {
+            let local_file = local.remove(&file.path);
+            let status = match &local_file {
+                Some(local_file) => file_status(
+                    file,
+                    local_file,
+                    local_hashes.get(&file.path).map(String::as_str),
+                ),
+                None => ArchiveStatus::MissingLocally,
+            };
+            ArchiveEntry {
+                path: file.path.clone(),
+                status,
+                remote_size: Some(file.size),
+                local_size: local_file.as_ref().map(|f| f.size),
+                remote_mod_time: file.mod_time.clone(),
+                local_mod_time: local_file.and_then(|f| f.modified).map(|t| t.to_rfc3339()),
+            }
+        })
+        .collect();
+
+    entries.extend(local.into_values().map(|file| ArchiveEntry {
+        path: file.path,
+        status: ArchiveStatus::LocalOnly,
+        remote_size: None,
+        local_size: Some(file.size),
+        remote_mod_time: None,
+        local_mod_time: file.modified.map(|t| t.to_rfc3339()),
+    }));
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+/// Count the states of the files below each folder
+fn folder_rollups(entries: &[ArchiveEntry]) -> Vec<FolderStatus> {
+    let mut folders: BTreeMap<&str, FolderStatus> = BTreeMap::new();
+    for entry in entries {
+        let mut folder = entry.path.as_str();
+        while let Some((parent, _)) = folder.rsplit_once('/') {
+            folders.entry(parent).or_default().add(entry.status);
+            folder = parent;
+        }
+        folders.entry("").or_default().add(entry.status);
+    }
+
+    folders
+        .into_iter()
+        .map(|(path, mut status)| {
+            status.path = path.to_string();
+            status
+        })
+        .collect()
+}
+
+/// Hash every file below a local folder through rclone
+async fn hash_local_files(
+    client: &rclone_sdk::Client,
+    dir: &Path,
+) -> Result<HashMap<String, String>, AppError> {
+    let response = client
+        .operations_hashsum(None, None, None, None, &download::local_fs(dir), "md5")
+        .await?
+        .into_inner();
+
+    // Lines are a hash and a path separated by two spaces
+    Ok(response
+        .hashsum
+        .iter()
+        .filter_map(|line| line.split_once("  "))
+        .map(|(hash, path)| (path.to_string(), hash.to_string()))
+        .collect())
+}
+
+/// Tag every file on Drive and in the archive with how the two compare,
+/// optionally comparing contents by MD5 instead of size and time
+#[tauri::command]
+pub async fn compare_archive(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    remote_config: String,
+    create_subfolder: bool,
+    check_hash: bool,
+) -> Result<ArchiveComparison, AppError> {
+    let (loaded, _) = manifest::load(&app, &source, &remote_config).await?;
+    let dst_dir = download::destination_path(&destination, create_subfolder);
+
+    let local_files = if dst_dir.is_dir() {
+        let dir = dst_dir.clone();
+        tauri::async_runtime::spawn_blocking(move || local::scan_local_tree(&dir))
+            .await
+            .map_err(|e| AppError::Internal(format!("Scanning archive failed: {}", e)))??
+    } else {
+        Vec::new()
+    };
+
+    let local_hashes = if check_hash && !local_files.is_empty() {
+        let client = rclone::get_sdk_client(&app).await?;
+        hash_local_files(&client, &dst_dir).await?
+    } else {
+        HashMap::new()
+    };
+
+    let entries = compare(&loaded.manifest.entries, local_files, &local_hashes);
+    Ok(ArchiveComparison {
+        folders: folder_rollups(&entries),
+        entries,
+        refreshed_at: loaded.manifest.refreshed_at.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIME: &str = "2026-01-02T03:04:05Z";
+
+    fn remote(path: &str, size: i64, md5: Option<&str>) -> GdriveFile {
+        GdriveFile {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap().to_string(),
+            size,
+            mod_time: Some(TIME.to_string()),
+            hashes: md5
+                .map(|md5| [("md5".to_string(), md5.to_string())].into())
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    fn local(path: &str, size: u64, offset_secs: i64) -> LocalFile {
+        LocalFile {
+            path: path.to_string(),
+            size,
+            modified: parse_time(Some(TIME)).map(|t| t + chrono::Duration::seconds(offset_secs)),
+        }
+    }
+
+    fn statuses(entries: &[ArchiveEntry]) -> Vec<(&str, ArchiveStatus)> {
+        entries
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.status))
+            .collect()
+    }
+
+    #[test]
+    fn files_are_compared_by_size_and_time() {
+        let remote = vec![
+            remote("album/same.mp3", 10, None),
+            remote("album/new.mp3", 10, None),
+            remote("album/changed.mp3", 10, None),
+            remote("album/edited.mp3", 10, None),
+            GdriveFile {
+                is_dir: true,
+                ..remote("album", 0, None)
+            },
+        ];
+        let local = vec![
+            local("album/same.mp3", 10, 0),
+            local("album/changed.mp3", 12, -60),
+            local("album/edited.mp3", 12, 60),
+            local("extra.mp3", 1, 0),
+        ];
+
+        let entries = compare(&remote, local, &HashMap::new());
+
+        assert_eq!(
+            statuses(&entries),
+            [
+                ("album/changed.mp3", ArchiveStatus::Outdated),
+                ("album/edited.mp3", ArchiveStatus::Conflicting),
+                ("album/new.mp3", ArchiveStatus::MissingLocally),
+                ("album/same.mp3", ArchiveStatus::UpToDate),
+                ("extra.mp3", ArchiveStatus::LocalOnly),
+            ]
+        );
+        assert_eq!(entries[2].local_size, None);
+        assert_eq!(entries[4].remote_size, None);
+    }
+
+    #[test]
+    fn hashes_override_size_and_time() {
+        let remote = vec![
+            remote("touched.mp3", 10, Some("AAAA")),
+            remote("corrupt.mp3", 10, Some("aaaa")),
+        ];
+        let local = vec![local("touched.mp3", 10, -60), local("corrupt.mp3", 10, 0)];
+        let hashes = HashMap::from([
+            ("touched.mp3".to_string(), "aaaa".to_string()),
+            ("corrupt.mp3".to_string(), "bbbb".to_string()),
+        ]);
+
+        let entries = compare(&remote, local, &hashes);
+
+        assert_eq!(
+            statuses(&entries),
+            [
+                ("corrupt.mp3", ArchiveStatus::Outdated),
+                ("touched.mp3", ArchiveStatus::UpToDate),
+            ]
+        );
+    }
+
+    #[test]
+    fn folders_roll_up_the_files_below_them() {
+        let remote = vec![
+            remote("a/b/new.mp3", 1, None),
+            remote("a/same.mp3", 1, None),
+        ];
+        let local = vec![local("a/same.mp3", 1, 0), local("c.mp3", 1, 0)];
+
+        let folders = folder_rollups(&compare(&remote, local, &HashMap::new()));
+
+        let paths: Vec<&str> = folders.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["", "a", "a/b"]);
+        assert_eq!(
+            (
+                folders[0].up_to_date,
+                folders[0].missing_locally,
+                folders[0].local_only
+            ),
+            (1, 1, 1)
+        );
+        assert_eq!((folders[1].up_to_date, folders[1].missing_locally), (1, 1));
+        assert_eq!(folders[2].missing_locally, 1);
+    }
+}
@@ -1,17 +1,119 @@
+use super::backups;
+use super::history::{FailedFile, RunOrigin, RunRecorder};
+use super::tuning::{self, TransferOptions};
 use super::utils::parse_gdrive_id;
 use crate::api::rclone;
+use crate::error::AppError;
+use chrono::NaiveDateTime;
 use rclone_sdk::ClientInfo;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tokio::sync::Mutex;
 use tokio::time::sleep;
 
+/// Name prefix of the folders old files are moved to during a sync
+pub(super) const BACKUP_PREFIX: &str = "Backup-KAR-";
+/// Format of the timestamp following the backup prefix
+pub(super) const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Maximum number of logged errors included in a failure message
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// The rclone jobs being waited for, and whether each was stopped on
+/// request, so its failure is reported as a cancellation. A job is only
+/// tracked while it is polled, so stopping a job that already finished
+/// leaves nothing behind.
+#[derive(Default)]
+pub struct CancelledJobs(Mutex<HashMap<i64, bool>>);
+
+impl CancelledJobs {
+    /// Start tracking a job, before anything can stop it
+    pub(super) async fn track(&self, jobid: i64) {
+        self.0.lock().await.entry(jobid).or_insert(false);
+    }
+
+    /// Mark a tracked job as stopped or not. Untracked jobs are ignored.
+    async fn set_stopped(&self, jobid: i64, stopped: bool) {
+        if let Some(flag) = self.0.lock().await.get_mut(&jobid) {
+            *flag = stopped;
+        }
+    }
+
+    /// Stop tracking a job, returning whether it was stopped on request
+    async fn untrack(&self, jobid: i64) -> bool {
+        self.0.lock().await.remove(&jobid).unwrap_or(false)
+    }
+}
+
+/// How a polled rclone job ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    Completed,
+    Cancelled,
+    /// The RC server exited unexpectedly while the job was running
+    Interrupted,
+}
+
 /// Result from executing a sync job
-struct SyncJobResult {
-    deletes: i64,
-    checks: i64,
-    transfers: i64,
-    errors: i64,
+#[derive(Debug, Clone, Default)]
+pub(super) struct SyncJobResult {
+    pub deletes: i64,
+    pub checks: i64,
+    pub transfers: i64,
+    pub renames: i64,
+    pub bytes: i64,
+    pub errors: i64,
+    /// Seconds the job ran for
+    pub elapsed_time: f64,
+    /// Whether an error stopped the whole job rather than single files
+    pub fatal_error: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DownloadStatus {
+    Completed,
+    /// The job finished, but some files could not be transferred
+    PartiallyFailed,
+}
+
+/// Outcome of a finished download
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadSummary {
+    pub status: DownloadStatus,
+    pub transfers: i64,
+    pub checks: i64,
+    pub deletes: i64,
+    pub renames: i64,
+    pub bytes: i64,
+    /// Seconds the download took
+    pub elapsed_time: f64,
+    pub errors: i64,
+    pub failed_files: Vec<FailedFile>,
+}
+
+impl DownloadSummary {
+    pub(super) fn new(stats: SyncJobResult, failed_files: Vec<FailedFile>) -> Self {
+        Self {
+            status: if stats.errors > 0 {
+                DownloadStatus::PartiallyFailed
+            } else {
+                DownloadStatus::Completed
+            },
+            transfers: stats.transfers,
+            checks: stats.checks,
+            deletes: stats.deletes,
+            renames: stats.renames,
+            bytes: stats.bytes,
+            elapsed_time: stats.elapsed_time,
+            errors: stats.errors,
+            failed_files,
+        }
+    }
 }
 
 /// Result from a dry run check
@@ -20,10 +122,50 @@ pub struct DryRunResult {
     pub would_delete: bool,
     pub deleted_files: Vec<String>,
     pub stats: String,
+    pub new_files: Vec<DryRunFile>,
+    pub updated_files: Vec<DryRunUpdate>,
+    pub renamed_files: Vec<DryRunRename>,
+    pub removed_files: Vec<DryRunFile>,
+    pub totals: DryRunTotals,
+}
+
+/// A file a dry run would copy or delete
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DryRunFile {
+    pub path: String,
+    pub size: i64,
+}
+
+/// An existing local file a dry run would overwrite
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DryRunUpdate {
+    pub path: String,
+    pub old_size: i64,
+    pub new_size: i64,
+}
+
+/// A local file a dry run would rename instead of downloading again. The
+/// new name is missing when rclone only logged the move.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DryRunRename {
+    pub from: String,
+    pub to: Option<String>,
+    pub size: i64,
+}
+
+/// Bytes affected by each kind of planned action
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DryRunTotals {
+    pub new_bytes: i64,
+    pub updated_bytes: i64,
+    pub renamed_bytes: i64,
+    pub removed_bytes: i64,
 }
 
 /// Configuration for a Google Drive download operation
-struct DownloadConfig {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadConfig {
     source: String,
     destination: String,
     remote_config: String,
@@ -33,17 +175,35 @@ struct DownloadConfig {
     create_backup: bool,
     delete_excluded: bool,
     track_renames: bool,
+    #[serde(default)]
+    transfer_options: TransferOptions,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resume: Option<ResumePoint>,
+}
+
+/// Progress of a queued download kept across attempts, so a paused or
+/// interrupted download doesn't start over
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumePoint {
+    /// Backup folder of the first attempt, reused so one download keeps
+    /// its replaced files in one folder
+    pub backup_path: Option<String>,
+    /// Files already copied, which later attempts leave out
+    pub transferred: Vec<String>,
 }
 
 /// Paths for source and destination filesystems
-struct FilesystemPaths {
-    src_fs: String,
-    dst_fs: String,
-    backup_path: Option<String>,
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesystemPaths {
+    pub src_fs: String,
+    pub dst_fs: String,
+    pub backup_path: Option<String>,
 }
 
 impl DownloadConfig {
-    fn new(
+    pub fn new(
         source: String,
         destination: String,
         remote_config: Option<String>,
@@ -53,54 +213,134 @@ impl DownloadConfig {
         create_backup: bool,
         delete_excluded: bool,
         track_renames: bool,
-    ) -> Result<Self, String> {
-        let remote_config = remote_config
-            .ok_or("Remote configuration is required. Please authorize first.".to_string())?;
-
-        Ok(Self {
+    ) -> Result<Self, AppError> {
+        let config = Self {
             source,
             destination,
-            remote_config,
+            remote_config: remote_config.ok_or(AppError::AuthRequired)?,
             sync_mode,
             create_subfolder,
             selected_files,
             create_backup,
             delete_excluded,
             track_renames,
-        })
+            transfer_options: TransferOptions::default(),
+            resume: None,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Set bandwidth and parallelism options for the download
+    pub fn with_transfer_options(mut self, options: TransferOptions) -> Result<Self, AppError> {
+        options.validate()?;
+        self.transfer_options = options;
+        Ok(self)
+    }
+
+    /// Check a configuration, e.g. one received as a whole from the frontend
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.remote_config.is_empty() {
+            return Err(AppError::AuthRequired);
+        }
+        if self.source.trim().is_empty() {
+            return Err(AppError::InvalidSource(
+                "No source folder given".to_string(),
+            ));
+        }
+        self.transfer_options.validate()
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn sync_mode(&self) -> bool {
+        self.sync_mode
+    }
+
+    pub fn selected_files(&self) -> Option<&[String]> {
+        self.selected_files.as_deref()
+    }
+
+    pub fn transfer_options(&self) -> &TransferOptions {
+        &self.transfer_options
+    }
+
+    pub fn resume_point(&self) -> Option<&ResumePoint> {
+        self.resume.as_ref()
+    }
+
+    /// Keep the backup folder of the first attempt for later ones
+    pub(super) fn keep_backup_path(&mut self, backup_path: Option<String>) {
+        let resume = self.resume.get_or_insert_default();
+        if resume.backup_path.is_none() {
+            resume.backup_path = backup_path;
+        }
+    }
+
+    /// Leave files copied by an earlier attempt out of later ones
+    pub(super) fn skip_transferred(&mut self, files: impl IntoIterator<Item = String>) {
+        let resume = self.resume.get_or_insert_default();
+        resume.transferred.extend(files);
+        resume.transferred.sort();
+        resume.transferred.dedup();
+    }
+
+    /// Start over on the next attempt, with a new backup folder
+    pub(super) fn forget_progress(&mut self) {
+        self.resume = None;
+    }
+
+    /// The rclone endpoint used to run this download
+    pub fn endpoint(&self) -> &'static str {
+        if self.sync_mode {
+            "/sync/sync"
+        } else {
+            "/sync/copy"
+        }
     }
 
     /// Build filesystem paths for source and destination
-    fn build_filesystem_paths(&self) -> Result<FilesystemPaths, String> {
+    pub fn build_filesystem_paths(&self) -> Result<FilesystemPaths, AppError> {
+        self.build_filesystem_paths_at(chrono::Local::now().naive_local(), cfg!(windows))
+    }
+
+    /// Build filesystem paths with a fixed backup time, writing local paths
+    /// the way rclone expects them on Windows or elsewhere, whichever
+    /// platform this runs on
+    pub fn build_filesystem_paths_at(
+        &self,
+        backup_time: NaiveDateTime,
+        windows: bool,
+    ) -> Result<FilesystemPaths, AppError> {
         let root_id = parse_gdrive_id(&self.source);
-        let src_fs = format!("{},root_folder_id={}:", self.remote_config, root_id);
+        let mut src_fs = format!("{},root_folder_id={}", self.remote_config, root_id);
+        if let Some(ref chunk_size) = self.transfer_options.chunk_size {
+            src_fs.push_str(&format!(",chunk_size={}", chunk_size));
+        }
+        src_fs.push(':');
 
         let dst_path = self.build_destination_path();
         let mut dst_fs = dst_path.to_string_lossy().to_string();
 
-        // Windows: Avoid `C:` being interpreted as a remote, use UNC paths
-        if cfg!(windows) {
-            let mut path_str = dst_fs.replace("/", "\\");
-            if !path_str.starts_with("\\\\?\\") {
-                path_str = format!("\\\\?\\{}", path_str);
-            }
-            dst_fs = path_str;
-        }
-
-        let backup_path = if self.create_backup {
-            let mut backup = self.build_backup_path(&dst_path)?;
-            if cfg!(windows) {
-                let mut path_str = backup.replace("/", "\\");
-                if !path_str.starts_with("\\\\?\\") {
-                    path_str = format!("\\\\?\\{}", path_str);
-                }
-                backup = path_str;
-            }
-            Some(backup)
-        } else {
-            None
+        let kept_backup = self.resume.as_ref().and_then(|r| r.backup_path.clone());
+        let mut backup_path = match kept_backup {
+            Some(backup) if self.create_backup => Some(backup),
+            _ if self.create_backup => Some(self.build_backup_path(&dst_path, backup_time)?),
+            _ => None,
         };
 
+        if windows {
+            dst_fs = to_unc_path(&dst_fs);
+            backup_path = backup_path.map(|backup| to_unc_path(&backup));
+        } else if cfg!(windows) {
+            // Built on Windows for another platform, e.g. in snapshot tests,
+            // so the separators joined in above have to be undone
+            dst_fs = dst_fs.replace('\\', "/");
+            backup_path = backup_path.map(|backup| backup.replace('\\', "/"));
+        }
+
         Ok(FilesystemPaths {
             src_fs,
             dst_fs,
@@ -109,39 +349,30 @@ impl DownloadConfig {
     }
 
     /// Build the destination path, optionally adding a subfolder
-    fn build_destination_path(&self) -> PathBuf {
-        let mut dst_path = PathBuf::from(&self.destination);
-
-        if self.create_subfolder {
-            let already_has_subfolder = dst_path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .map(|name| name == "An-Unofficial-Neuro-Karaoke-Archive")
-                .unwrap_or(false);
-
-            if !already_has_subfolder {
-                dst_path.push("An-Unofficial-Neuro-Karaoke-Archive");
-            }
-        }
-
-        dst_path
+    pub fn build_destination_path(&self) -> PathBuf {
+        destination_path(&self.destination, self.create_subfolder)
     }
 
     /// Build backup path one level above the destination
-    fn build_backup_path(&self, dst_path: &PathBuf) -> Result<String, String> {
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
-        let backup_name = format!("Backup-KAR-{}", timestamp);
+    pub fn build_backup_path(
+        &self,
+        dst_path: &Path,
+        backup_time: NaiveDateTime,
+    ) -> Result<String, AppError> {
+        let timestamp = backup_time.format(BACKUP_TIMESTAMP_FORMAT).to_string();
+        let backup_name = format!("{}{}", BACKUP_PREFIX, timestamp);
 
-        let parent_path = dst_path
-            .parent()
-            .ok_or("Cannot get parent directory of destination")?;
+        let parent_path = dst_path.parent().ok_or(AppError::InvalidDestination(
+            "Cannot get parent directory of destination".to_string(),
+        ))?;
 
         let backup_full_path = parent_path.join(backup_name);
         Ok(backup_full_path.to_string_lossy().to_string())
     }
 
-    /// Build the request body for the sync operation
-    fn build_request_body(&self, paths: &FilesystemPaths) -> serde_json::Value {
+    /// Build the request body for the sync operation. The bandwidth limit
+    /// isn't part of it, as rclone applies one limit to the whole server.
+    pub fn build_request_body(&self, paths: &FilesystemPaths) -> serde_json::Value {
         let mut body = serde_json::json!({
             "_async": true,
             "srcFs": paths.src_fs,
@@ -163,11 +394,29 @@ impl DownloadConfig {
             );
         }
 
+        if let Some(transfers) = self.transfer_options.transfers {
+            config.insert("Transfers".to_string(), serde_json::json!(transfers));
+        }
+        if let Some(checkers) = self.transfer_options.checkers {
+            config.insert("Checkers".to_string(), serde_json::json!(checkers));
+        }
+
         if !config.is_empty() {
             body["_config"] = serde_json::json!(config);
         }
 
-        if let Some(ref files) = self.selected_files {
+        // Deleting excluded files would delete those already copied, so
+        // such downloads check every file again instead
+        let deletes_excluded = self.delete_excluded && self.selected_files.is_some();
+        let skipped = self
+            .resume
+            .as_ref()
+            .map(|resume| resume.transferred.as_slice())
+            .filter(|files| !files.is_empty() && !deletes_excluded);
+
+        if let Some(skipped) = skipped {
+            body["_filter"] = build_resume_filter(skipped, self.selected_files.as_deref());
+        } else if let Some(ref files) = self.selected_files {
             let mut filter = build_file_filter(files);
             // Delete files not found in the filter
             if self.delete_excluded {
@@ -182,8 +431,47 @@ impl DownloadConfig {
     }
 }
 
+/// The folder a download writes to, optionally adding a subfolder
+pub fn destination_path(destination: &str, create_subfolder: bool) -> PathBuf {
+    let mut dst_path = PathBuf::from(destination);
+
+    if create_subfolder {
+        let already_has_subfolder = dst_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name == "An-Unofficial-Neuro-Karaoke-Archive")
+            .unwrap_or(false);
+
+        if !already_has_subfolder {
+            dst_path.push("An-Unofficial-Neuro-Karaoke-Archive");
+        }
+    }
+
+    dst_path
+}
+
+/// A local path as an rclone filesystem on this platform
+pub(super) fn local_fs(path: &Path) -> String {
+    let path = path.to_string_lossy().to_string();
+    if cfg!(windows) {
+        to_unc_path(&path)
+    } else {
+        path
+    }
+}
+
+/// Windows: Avoid `C:` being interpreted as a remote, use UNC paths
+fn to_unc_path(path: &str) -> String {
+    let path = path.replace("/", "\\");
+    if path.starts_with("\\\\?\\") {
+        path
+    } else {
+        format!("\\\\?\\{}", path)
+    }
+}
+
 /// Build filter rules for selected files
-fn build_file_filter(files: &[String]) -> serde_json::Value {
+pub fn build_file_filter(files: &[String]) -> serde_json::Value {
     if files.is_empty() {
         return serde_json::json!({});
     }
@@ -200,41 +488,225 @@ fn build_file_filter(files: &[String]) -> serde_json::Value {
     })
 }
 
-/// Start the sync/copy operation, wait for completion, and return the results
+/// Build filter rules leaving out files copied by an earlier attempt, then
+/// keeping only the selected files, if any
+fn build_resume_filter(transferred: &[String], selected: Option<&[String]>) -> serde_json::Value {
+    let mut rules: Vec<String> = transferred
+        .iter()
+        .map(|f| format!("- /{}", escape_glob(f.trim_start_matches('/'))))
+        .collect();
+
+    if let Some(files) = selected.filter(|files| !files.is_empty()) {
+        for f in files {
+            let clean_f = escape_glob(f.trim_start_matches('/'));
+            rules.push(format!("+ /{}", clean_f));
+            rules.push(format!("+ /{}/**", clean_f));
+        }
+        rules.push("- **".to_string());
+    }
+
+    serde_json::json!({
+        "FilterRule": rules
+    })
+}
+
+/// Escape the characters rclone filter rules treat as patterns
+fn escape_glob(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Run a sync job to its end, returning its result and the files it was
+/// done with
 async fn start_sync_job(
+    app: &tauri::AppHandle,
+    client: &rclone_sdk::Client,
+    config: &DownloadConfig,
+    body: &serde_json::Value,
+) -> (
+    Result<SyncJobResult, AppError>,
+    Vec<rclone::TransferredFile>,
+) {
+    // Capture the current log offset so errors can be attributed to this job
+    let start_offset = rclone::LogManager::get_current_offset(app).await;
+
+    let jobid = match submit_tuned_job(app, client, config, body).await {
+        Ok(jobid) => jobid,
+        Err(e) => return (Err(e), Vec::new()),
+    };
+    let cancelled = app.state::<CancelledJobs>();
+    cancelled.track(jobid).await;
+    let _ = app.emit("download-job-started", jobid);
+    let monitor = rclone::spawn_job_monitor(app, client, jobid);
+
+    // Poll for job completion
+    let outcome = poll_job_completion(client, &cancelled, jobid).await;
+    let transferred = monitor.transferred().await;
+    let result = match job_result(client, jobid, &outcome).await {
+        Err(e) if outcome.is_err() => Err(with_logged_errors(app, start_offset, e).await),
+        result => result,
+    };
+    (result, transferred)
+}
+
+/// Turn the outcome of a polled job into its final stats or an error. A job
+/// that failed only because some files did, while the rest were transferred,
+/// still returns its stats with the failures counted in `errors`.
+pub(super) async fn job_result(
+    client: &rclone_sdk::Client,
+    jobid: i64,
+    outcome: &Result<JobOutcome, AppError>,
+) -> Result<SyncJobResult, AppError> {
+    match outcome {
+        Ok(JobOutcome::Completed) => get_job_stats(client, jobid).await,
+        Ok(JobOutcome::Cancelled) => Err(AppError::Cancelled("Download cancelled".to_string())),
+        Ok(JobOutcome::Interrupted) => Err(AppError::Interrupted),
+        Err(e @ AppError::Cancelled(_)) => Err(e.clone()),
+        Err(e) => match get_job_stats(client, jobid).await {
+            Ok(stats) if stats.errors > 0 && !stats.fatal_error => Ok(stats),
+            _ => Err(e.clone()),
+        },
+    }
+}
+
+/// Explain a generic rclone failure with the errors it logged since the
+/// given offset. A logged cause with its own error kind, like an exhausted
+/// quota, replaces the failure, otherwise the entries are appended to it.
+async fn with_logged_errors(
+    app: &tauri::AppHandle,
+    start_offset: u64,
+    error: AppError,
+) -> AppError {
+    let AppError::Rclone(message) = error else {
+        return error;
+    };
+    let entries = match rclone::LogManager::parse_errors(app, start_offset).await {
+        Ok(entries) if !entries.is_empty() => entries,
+        _ => return AppError::Rclone(message),
+    };
+
+    let details: Vec<String> = entries
+        .iter()
+        .take(MAX_REPORTED_ERRORS)
+        .map(|entry| entry.to_string())
+        .collect();
+    let cause = details
+        .iter()
+        .map(AppError::from_rclone_message)
+        .find(|cause| !matches!(cause, AppError::Rclone(_)));
+
+    cause.unwrap_or(AppError::Rclone(format!(
+        "{}\n{}",
+        message,
+        details.join("\n")
+    )))
+}
+
+/// Start an rclone operation, like a sync or copy, in the background and
+/// return its rclone job id
+pub(super) async fn submit_sync_job(
     client: &rclone_sdk::Client,
     body: &serde_json::Value,
     endpoint: &str,
-) -> Result<SyncJobResult, String> {
+) -> Result<i64, AppError> {
     let response = client
         .client()
         .post(format!("{}{}", client.baseurl(), endpoint))
         .json(body)
         .send()
         .await
-        .map_err(|e| format!("Sync start failed: {}", e))?;
+        .map_err(|e| AppError::request("Sync start failed", e))?;
 
     if !response.status().is_success() {
+        let status = response.status().as_u16();
         let err_text = response.text().await.unwrap_or_default();
-        return Err(format!("Sync start failed: {}", err_text));
+        return Err(AppError::from_rc_body(status, &err_text));
     }
 
     let result: rclone_sdk::types::SyncCopyResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse sync response: {}", e))?;
+        .map_err(|e| AppError::Internal(format!("Failed to parse sync response: {}", e)))?;
 
-    let jobid = result.jobid.ok_or("No jobid returned".to_string())?;
+    result
+        .jobid
+        .ok_or(AppError::Rclone("No jobid returned".to_string()))
+}
 
-    // Poll for job completion
-    poll_job_completion(client, jobid).await?;
+/// Submit a queued download to rclone with the paths built for it,
+/// returning the client, rclone job id and the job's monitor
+pub(super) async fn start_queued_download(
+    app: &tauri::AppHandle,
+    config: &DownloadConfig,
+    paths: &FilesystemPaths,
+) -> Result<(rclone_sdk::Client, i64, rclone::JobMonitor), AppError> {
+    let client = rclone::get_sdk_client(app).await?;
+    let body = config.build_request_body(paths);
 
-    // Get final stats
-    get_job_stats(client, jobid).await
+    let jobid = submit_tuned_job(app, &client, config, &body).await?;
+    let monitor = rclone::spawn_job_monitor(app, &client, jobid);
+    Ok((client, jobid, monitor))
+}
+
+/// Submit a download with its bandwidth limit applied for as long as it runs
+async fn submit_tuned_job(
+    app: &tauri::AppHandle,
+    client: &rclone_sdk::Client,
+    config: &DownloadConfig,
+    body: &serde_json::Value,
+) -> Result<i64, AppError> {
+    let lease = tuning::apply_bandwidth_limit(client, config.transfer_options()).await?;
+    let jobid = match submit_sync_job(client, body, config.endpoint()).await {
+        Ok(jobid) => jobid,
+        Err(e) => {
+            if let Some(lease) = lease {
+                tuning::release_bandwidth_limit(client, lease).await;
+            }
+            return Err(e);
+        }
+    };
+    tuning::spawn_bandwidth_schedule(app, client, jobid, lease);
+    Ok(jobid)
+}
+
+/// Stop a single rclone job, leaving the server and other jobs running
+pub(super) async fn stop_job(
+    client: &rclone_sdk::Client,
+    cancelled: &CancelledJobs,
+    jobid: i64,
+) -> Result<(), AppError> {
+    cancelled.set_stopped(jobid, true).await;
+
+    if let Err(e) = client.job_stop(None, jobid as f64).await {
+        cancelled.set_stopped(jobid, false).await;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Poll for job completion. A job stopped through `stop_job` while it was
+/// polled ends as cancelled, however rclone reports it.
+pub(super) async fn poll_job_completion(
+    client: &rclone_sdk::Client,
+    cancelled: &CancelledJobs,
+    jobid: i64,
+) -> Result<JobOutcome, AppError> {
+    cancelled.track(jobid).await;
+    let outcome = poll_job_status(client, jobid).await;
+    if cancelled.untrack(jobid).await {
+        return Ok(JobOutcome::Cancelled);
+    }
+    outcome
 }
 
-/// Poll for job completion
-async fn poll_job_completion(client: &rclone_sdk::Client, jobid: i64) -> Result<(), String> {
+/// Poll a job's status until it finishes
+async fn poll_job_status(client: &rclone_sdk::Client, jobid: i64) -> Result<JobOutcome, AppError> {
     loop {
         let response_result = client
             .client()
@@ -247,35 +719,41 @@ async fn poll_job_completion(client: &rclone_sdk::Client, jobid: i64) -> Result<
 
         let response = match response_result {
             Ok(res) => res,
-            Err(e) => {
-                let err_str = e.to_string();
-                if err_str.contains("error sending request")
-                    || err_str.contains("connection refused")
-                {
-                    return Err("Download cancelled (server stopped)".to_string());
+            Err(_) => {
+                if rclone::server_state() == rclone::ServerState::Stopped {
+                    return Err(AppError::Cancelled(
+                        "Download cancelled (server stopped)".to_string(),
+                    ));
+                }
+                // A hiccup while our server is still up, try again
+                if rclone::is_session_client(client) && rclone::is_server_running().await {
+                    sleep(Duration::from_secs(1)).await;
+                    continue;
                 }
-                return Err(format!("Failed to check job status: {}", e));
+                return Ok(JobOutcome::Interrupted);
             }
         };
 
         if !response.status().is_success() {
+            let status = response.status().as_u16();
             let err_text = response.text().await.unwrap_or_default();
-            if err_text.contains("job not found") {
-                return Err("Download cancelled".to_string());
-            }
-            return Err(format!("Job status check failed: {}", err_text));
+            return Err(match AppError::from_rc_body(status, &err_text) {
+                // The job was removed, e.g. by stopping it
+                AppError::NotFound(_) => AppError::Cancelled("Download cancelled".to_string()),
+                e => e,
+            });
         }
 
         let status: rclone_sdk::types::JobStatusResponse = response
             .json()
             .await
-            .map_err(|e| format!("Failed to parse job status: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to parse job status: {}", e)))?;
 
         if status.finished {
             if !status.error.is_empty() {
-                return Err(format!("Job failed: {}", status.error));
+                return Err(AppError::from_rclone_message(status.error));
             }
-            return Ok(());
+            return Ok(JobOutcome::Completed);
         }
 
         sleep(Duration::from_secs(1)).await;
@@ -283,7 +761,7 @@ async fn poll_job_completion(client: &rclone_sdk::Client, jobid: i64) -> Result<
 }
 
 /// Get job statistics from rclone
-async fn get_job_stats(client: &rclone_sdk::Client, jobid: i64) -> Result<SyncJobResult, String> {
+async fn get_job_stats(client: &rclone_sdk::Client, jobid: i64) -> Result<SyncJobResult, AppError> {
     let stats_response = client
         .client()
         .post(format!("{}/core/stats", client.baseurl()))
@@ -292,18 +770,22 @@ async fn get_job_stats(client: &rclone_sdk::Client, jobid: i64) -> Result<SyncJo
         }))
         .send()
         .await
-        .map_err(|e| format!("Failed to get stats: {}", e))?;
+        .map_err(|e| AppError::request("Failed to get stats", e))?;
 
     let stats: serde_json::Value = stats_response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse stats: {}", e))?;
+        .map_err(|e| AppError::Internal(format!("Failed to parse stats: {}", e)))?;
 
     Ok(SyncJobResult {
         deletes: stats["deletes"].as_i64().unwrap_or(0),
         checks: stats["checks"].as_i64().unwrap_or(0),
         transfers: stats["transfers"].as_i64().unwrap_or(0),
+        renames: stats["renames"].as_i64().unwrap_or(0),
+        bytes: stats["bytes"].as_i64().unwrap_or(0),
         errors: stats["errors"].as_i64().unwrap_or(0),
+        elapsed_time: stats["elapsedTime"].as_f64().unwrap_or(0.0),
+        fatal_error: stats["fatalError"].as_bool().unwrap_or(false),
     })
 }
 
@@ -319,7 +801,8 @@ pub async fn download_gdrive(
     create_backup: bool,
     delete_excluded: bool,
     track_renames: bool,
-) -> Result<String, String> {
+    transfer_options: Option<TransferOptions>,
+) -> Result<DownloadSummary, AppError> {
     let config = DownloadConfig::new(
         source,
         destination,
@@ -330,23 +813,37 @@ pub async fn download_gdrive(
         create_backup,
         delete_excluded,
         track_renames,
-    )?;
+    )?
+    .with_transfer_options(transfer_options.unwrap_or_default())?;
 
-    let client = rclone::get_sdk_client(&app).await?;
+    run_download(&app, &config, RunOrigin::Manual).await
+}
+
+/// Run a download until it finishes and record it in the sync history,
+/// then apply the backup retention policy
+pub(super) async fn run_download(
+    app: &tauri::AppHandle,
+    config: &DownloadConfig,
+    origin: RunOrigin,
+) -> Result<DownloadSummary, AppError> {
     let paths = config.build_filesystem_paths()?;
     let body = config.build_request_body(&paths);
 
-    let endpoint = if config.sync_mode {
-        "/sync/sync"
-    } else {
-        "/sync/copy"
+    let mut recorder = RunRecorder::start(app, origin, config).await;
+    recorder.set_backup_path(paths.backup_path.clone());
+
+    let (result, transferred) = match rclone::get_sdk_client(app).await {
+        Ok(client) => start_sync_job(app, &client, config, &body).await,
+        Err(e) => (Err(e), Vec::new()),
     };
-    let _result = start_sync_job(&client, &body, endpoint).await?;
+    let run = recorder.finish(app, &result, &transferred).await;
 
-    Ok("Download completed successfully".to_string())
+    let stats = result?;
+    backups::prune_after_sync(app, config, paths.backup_path.as_deref()).await;
+    Ok(DownloadSummary::new(stats, run.failed))
 }
 
-/// Perform a dry run sync to detect what files would be deleted
+/// Perform a dry run sync to list every change a sync would make
 #[tauri::command]
 pub async fn check_dry_run(
     app: tauri::AppHandle,
@@ -357,7 +854,7 @@ pub async fn check_dry_run(
     selected_files: Option<Vec<String>>,
     delete_excluded: bool,
     track_renames: bool,
-) -> Result<DryRunResult, String> {
+) -> Result<DryRunResult, AppError> {
     let config = DownloadConfig::new(
         source,
         destination,
@@ -390,20 +887,388 @@ pub async fn check_dry_run(
     // Capture the current log offset to ignore previous logs
     let start_offset = rclone::LogManager::get_current_offset(&app).await;
 
-    let result = start_sync_job(&client, &body, "/sync/sync").await?;
+    let (result, _) = start_sync_job(&app, &client, &config, &body).await;
+    let result = result?;
 
     // Parse logs from the offset
-    let deleted_files = rclone::LogManager::parse_deleted_files(&app, start_offset).await?;
+    let actions = rclone::LogManager::parse_dry_run_actions(&app, start_offset).await?;
+    let mut dry_run = build_dry_run_result(&config.build_destination_path(), actions);
 
-    let would_delete = result.deletes > 0 || !deleted_files.is_empty();
-    let stats_summary = format!(
+    dry_run.would_delete = result.deletes > 0 || !dry_run.removed_files.is_empty();
+    dry_run.stats = format!(
         "Checks: {}, Transfers: {}, Deletes: {}, Errors: {}",
         result.checks, result.transfers, result.deletes, result.errors
     );
 
-    Ok(DryRunResult {
-        would_delete,
-        deleted_files,
-        stats: stats_summary,
-    })
+    Ok(dry_run)
+}
+
+/// Sort dry run actions into categories, using the local destination
+/// to tell new files from overwritten ones
+fn build_dry_run_result(dst_path: &Path, actions: Vec<rclone::DryRunAction>) -> DryRunResult {
+    let local_size = |path: &str| {
+        std::fs::metadata(dst_path.join(path))
+            .ok()
+            .filter(|m| m.is_file())
+            .map(|m| m.len() as i64)
+    };
+
+    let mut result = DryRunResult {
+        would_delete: false,
+        deleted_files: Vec::new(),
+        stats: String::new(),
+        new_files: Vec::new(),
+        updated_files: Vec::new(),
+        renamed_files: Vec::new(),
+        removed_files: Vec::new(),
+        totals: DryRunTotals::default(),
+    };
+
+    for action in actions {
+        match action {
+            rclone::DryRunAction::Copy { path, size } => {
+                let new_size = size.unwrap_or(0);
+                match local_size(&path) {
+                    Some(old_size) => {
+                        result.totals.updated_bytes += new_size;
+                        result.updated_files.push(DryRunUpdate {
+                            path,
+                            old_size,
+                            new_size,
+                        });
+                    }
+                    None => {
+                        result.totals.new_bytes += new_size;
+                        result.new_files.push(DryRunFile {
+                            path,
+                            size: new_size,
+                        });
+                    }
+                }
+            }
+            rclone::DryRunAction::Delete { path, size } => {
+                let size = size.or_else(|| local_size(&path)).unwrap_or(0);
+                result.totals.removed_bytes += size;
+                result.deleted_files.push(path.clone());
+                result.removed_files.push(DryRunFile { path, size });
+            }
+            rclone::DryRunAction::Rename { from, to, size } => {
+                // A move and the rename it belongs to are both logged
+                if let Some(rename) = result.renamed_files.iter_mut().find(|r| r.from == from) {
+                    if rename.to.is_none() {
+                        rename.to = to;
+                    }
+                    continue;
+                }
+                let size = size.or_else(|| local_size(&from)).unwrap_or(0);
+                result.totals.renamed_bytes += size;
+                result.renamed_files.push(DryRunRename { from, to, size });
+            }
+        }
+    }
+
+    result
+}
+
+/// Cancel a single running download by its rclone job id
+#[tauri::command]
+pub async fn cancel_download(app: tauri::AppHandle, jobid: i64) -> Result<(), AppError> {
+    let client = rclone::get_sdk_client(&app).await?;
+    stop_job(&client, &app.state::<CancelledJobs>(), jobid).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rclone::mock::MockRcServer;
+
+    fn config(sync_mode: bool) -> DownloadConfig {
+        DownloadConfig::new(
+            "https://drive.google.com/drive/folders/FOLDER_ID?usp=sharing".to_string(),
+            "/music".to_string(),
+            Some("gdrive".to_string()),
+            sync_mode,
+            false,
+            None,
+            false,
+            false,
+            false,
+        )
+        .unwrap()
+    }
+
+    async fn submit(server: &MockRcServer, config: &DownloadConfig) -> i64 {
+        let paths = config.build_filesystem_paths().unwrap();
+        let body = config.build_request_body(&paths);
+        submit_sync_job(&server.client(), &body, config.endpoint())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sync_job_is_polled_until_finished() {
+        let server = MockRcServer::start().await;
+        server.state().job_polls = 2;
+
+        let jobid = submit(&server, &config(true)).await;
+        let outcome = poll_job_completion(&server.client(), &CancelledJobs::default(), jobid).await;
+
+        assert_eq!(outcome.unwrap(), JobOutcome::Completed);
+        let job = server.state().jobs[&jobid].clone();
+        assert_eq!(job.path, "sync/sync");
+        assert_eq!(job.params["srcFs"], "gdrive,root_folder_id=FOLDER_ID:");
+        assert_eq!(job.params["_async"], true);
+        let polls = server
+            .call_paths()
+            .iter()
+            .filter(|path| *path == "job/status")
+            .count();
+        assert_eq!(polls, 3);
+    }
+
+    #[tokio::test]
+    async fn copy_mode_uses_sync_copy() {
+        let server = MockRcServer::start().await;
+
+        let jobid = submit(&server, &config(false)).await;
+
+        assert_eq!(server.state().jobs[&jobid].path, "sync/copy");
+    }
+
+    #[tokio::test]
+    async fn failed_job_is_classified_by_its_error() {
+        let server = MockRcServer::start().await;
+        server.state().job_error =
+            "googleapi: Error 403: The download quota for this file has been exceeded, \
+             downloadQuotaExceeded"
+                .to_string();
+
+        let jobid = submit(&server, &config(true)).await;
+        let outcome = poll_job_completion(&server.client(), &CancelledJobs::default(), jobid).await;
+
+        assert!(matches!(outcome, Err(AppError::QuotaExceeded(_))));
+    }
+
+    #[tokio::test]
+    async fn stopped_job_is_cancelled() {
+        let server = MockRcServer::start().await;
+        server.state().job_polls = u32::MAX;
+        let client = server.client();
+
+        let cancelled = CancelledJobs::default();
+
+        let jobid = submit(&server, &config(true)).await;
+        cancelled.track(jobid).await;
+        stop_job(&client, &cancelled, jobid).await.unwrap();
+        let outcome = poll_job_completion(&client, &cancelled, jobid).await;
+
+        assert_eq!(outcome.unwrap(), JobOutcome::Cancelled);
+        assert!(cancelled.0.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stopping_a_finished_job_leaves_nothing_tracked() {
+        let server = MockRcServer::start().await;
+        let client = server.client();
+        let cancelled = CancelledJobs::default();
+
+        let jobid = submit(&server, &config(true)).await;
+        let outcome = poll_job_completion(&client, &cancelled, jobid).await;
+        stop_job(&client, &cancelled, jobid).await.unwrap();
+
+        assert_eq!(outcome.unwrap(), JobOutcome::Completed);
+        assert!(cancelled.0.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_job_is_reported_as_cancelled() {
+        let server = MockRcServer::start().await;
+
+        let outcome = poll_job_completion(&server.client(), &CancelledJobs::default(), -1).await;
+
+        assert!(matches!(outcome, Err(AppError::Cancelled(_))));
+    }
+
+    #[tokio::test]
+    async fn stopping_an_unknown_job_fails() {
+        let server = MockRcServer::start().await;
+
+        let cancelled = CancelledJobs::default();
+        cancelled.track(-2).await;
+
+        let result = stop_job(&server.client(), &cancelled, -2).await;
+
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert!(!cancelled.untrack(-2).await);
+    }
+
+    #[tokio::test]
+    async fn job_stats_are_read_from_the_job_group() {
+        let server = MockRcServer::start().await;
+        server.state().stats = serde_json::json!({
+            "checks": 12,
+            "transfers": 3,
+            "deletes": 2,
+            "renames": 1,
+            "bytes": 2048,
+            "errors": 1,
+        })
+        .as_object()
+        .unwrap()
+        .clone();
+
+        let result = get_job_stats(&server.client(), 7).await.unwrap();
+
+        assert_eq!(
+            (
+                result.checks,
+                result.transfers,
+                result.deletes,
+                result.renames,
+                result.bytes,
+                result.errors
+            ),
+            (12, 3, 2, 1, 2048, 1)
+        );
+        let calls = &server.state().calls;
+        assert_eq!(calls[0].0, "core/stats");
+        assert_eq!(calls[0].1["group"], "job/7");
+    }
+
+    #[tokio::test]
+    async fn job_with_failed_files_is_a_partial_failure() {
+        let server = MockRcServer::start().await;
+        server.state().job_error = "2 errors: last error was: failed to open source".to_string();
+        server.state().stats = serde_json::json!({ "transfers": 5, "errors": 2 })
+            .as_object()
+            .unwrap()
+            .clone();
+        let client = server.client();
+
+        let jobid = submit(&server, &config(true)).await;
+        let outcome = poll_job_completion(&client, &CancelledJobs::default(), jobid).await;
+        let result = job_result(&client, jobid, &outcome).await.unwrap();
+        let summary = DownloadSummary::new(result, Vec::new());
+
+        assert!(outcome.is_err());
+        assert_eq!(summary.status, DownloadStatus::PartiallyFailed);
+        assert_eq!((summary.transfers, summary.errors), (5, 2));
+
+        // An error that stopped the whole job stays a failure
+        server
+            .state()
+            .stats
+            .insert("fatalError".to_string(), true.into());
+        let jobid = submit(&server, &config(true)).await;
+        let outcome = poll_job_completion(&client, &CancelledJobs::default(), jobid).await;
+
+        assert!(job_result(&client, jobid, &outcome).await.is_err());
+    }
+
+    #[test]
+    fn resumed_download_skips_copied_files_and_keeps_its_backup() {
+        let mut config = DownloadConfig::new(
+            "FOLDER_ID".to_string(),
+            "/music/archive".to_string(),
+            Some("gdrive".to_string()),
+            true,
+            false,
+            Some(vec!["album".to_string()]),
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+        let first_time = NaiveDateTime::parse_from_str("20260102_030405", "%Y%m%d_%H%M%S").unwrap();
+        let first = config.build_filesystem_paths_at(first_time, false).unwrap();
+
+        config.keep_backup_path(first.backup_path.clone());
+        config.skip_transferred(["album/b [live].mp3".to_string(), "album/a.mp3".to_string()]);
+        config.skip_transferred(["album/a.mp3".to_string()]);
+        let resumed = config
+            .build_filesystem_paths_at(first_time + chrono::Duration::hours(1), false)
+            .unwrap();
+        let body = config.build_request_body(&resumed);
+
+        assert_eq!(
+            resumed.backup_path.as_deref(),
+            Some("/music/Backup-KAR-20260102_030405")
+        );
+        assert_eq!(
+            body["_filter"],
+            serde_json::json!({
+                "FilterRule": [
+                    "- /album/a.mp3",
+                    "- /album/b \\[live\\].mp3",
+                    "+ /album",
+                    "+ /album/**",
+                    "- **"
+                ]
+            })
+        );
+
+        // Retrying starts over with a new backup folder
+        config.forget_progress();
+        let retried = config
+            .build_filesystem_paths_at(first_time + chrono::Duration::hours(1), false)
+            .unwrap();
+        assert_eq!(
+            retried.backup_path.as_deref(),
+            Some("/music/Backup-KAR-20260102_040405")
+        );
+        assert_eq!(
+            config.build_request_body(&retried)["_filter"],
+            serde_json::json!({ "IncludeRule": ["/album", "/album/**"] })
+        );
+    }
+
+    #[test]
+    fn dry_run_actions_are_sorted_by_local_state() {
+        let dst = std::env::temp_dir().join(format!("dry-run-{}", std::process::id()));
+        std::fs::create_dir_all(dst.join("album")).unwrap();
+        std::fs::write(dst.join("album/old.mp3"), [0; 10]).unwrap();
+        std::fs::write(dst.join("album/renamed.mp3"), [0; 4]).unwrap();
+
+        let actions = vec![
+            rclone::DryRunAction::Copy {
+                path: "album/new.mp3".to_string(),
+                size: Some(100),
+            },
+            rclone::DryRunAction::Copy {
+                path: "album/old.mp3".to_string(),
+                size: Some(20),
+            },
+            rclone::DryRunAction::Delete {
+                path: "album/gone.mp3".to_string(),
+                size: Some(5),
+            },
+            rclone::DryRunAction::Rename {
+                from: "album/renamed.mp3".to_string(),
+                to: None,
+                size: None,
+            },
+            rclone::DryRunAction::Rename {
+                from: "album/renamed.mp3".to_string(),
+                to: Some("album/moved.mp3".to_string()),
+                size: None,
+            },
+        ];
+        let result = build_dry_run_result(&dst, actions);
+        std::fs::remove_dir_all(&dst).unwrap();
+
+        assert_eq!(result.new_files.len(), 1);
+        assert_eq!(result.updated_files[0].old_size, 10);
+        assert_eq!(result.updated_files[0].new_size, 20);
+        assert_eq!(result.deleted_files, vec!["album/gone.mp3"]);
+        assert_eq!(result.renamed_files.len(), 1);
+        assert_eq!(result.renamed_files[0].size, 4);
+        assert_eq!(
+            result.renamed_files[0].to.as_deref(),
+            Some("album/moved.mp3")
+        );
+        assert_eq!(result.totals.new_bytes, 100);
+        assert_eq!(result.totals.updated_bytes, 20);
+        assert_eq!(result.totals.removed_bytes, 5);
+        assert_eq!(result.totals.renamed_bytes, 4);
+    }
 }
@@ -0,0 +1,702 @@
+use super::download::{DownloadConfig, SyncJobResult};
+use crate::api::rclone::{LogManager, SyncAction, TransferredFile};
+use crate::error::AppError;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Number of runs returned by a listing when no limit is given
+const DEFAULT_LIST_LIMIT: usize = 100;
+
+/// Serializes appends, so runs finishing together don't interleave lines
+static HISTORY_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+/// What started a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum RunOrigin {
+    Manual,
+    Queue {
+        job_id: u64,
+    },
+    Schedule {
+        profile_id: u64,
+        profile_name: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncRunStatus {
+    Completed,
+    /// Finished, but some files could not be transferred
+    PartiallyFailed,
+    Failed,
+    Cancelled,
+    Interrupted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenamedFile {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedFile {
+    pub path: Option<String>,
+    pub reason: String,
+}
+
+/// A finished run without its file lists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRunSummary {
+    pub id: String,
+    pub origin: RunOrigin,
+    pub source: String,
+    pub destination: String,
+    pub sync_mode: bool,
+    pub started_at: String,
+    pub finished_at: String,
+    pub status: SyncRunStatus,
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+    pub transfers: i64,
+    pub checks: i64,
+    pub deletes: i64,
+    pub renames: i64,
+    pub bytes: i64,
+    pub errors: i64,
+    pub backup_path: Option<String>,
+}
+
+/// A finished run and every change it made to the archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRun {
+    #[serde(flatten)]
+    pub summary: SyncRunSummary,
+    /// Files downloaded that weren't in the archive before
+    pub copied: Vec<String>,
+    /// Files downloaded over an older version
+    pub replaced: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Files moved out of the archive into the backup folder
+    pub backed_up: Vec<String>,
+    pub renamed: Vec<RenamedFile>,
+    pub failed: Vec<FailedFile>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileEventKind {
+    Added,
+    Replaced,
+    Deleted,
+    BackedUp,
+    RenamedFrom,
+    RenamedTo,
+    Failed,
+}
+
+/// Something that happened to a file during a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileEvent {
+    pub run_id: String,
+    pub time: String,
+    pub path: String,
+    pub kind: FileEventKind,
+}
+
+/// Runs being recorded right now
+static ACTIVE_RUNS: AtomicUsize = AtomicUsize::new(0);
+/// Runs started since the app started
+static STARTED_RUNS: AtomicU64 = AtomicU64::new(0);
+
+/// Counts a run as active for as long as it is being recorded, to tell
+/// whether it had the rclone log to itself
+struct ActiveRun {
+    number: u64,
+    /// Another run was already active when this one started
+    joined_others: bool,
+}
+
+impl ActiveRun {
+    fn start() -> Self {
+        Self {
+            number: STARTED_RUNS.fetch_add(1, Ordering::SeqCst),
+            joined_others: ACTIVE_RUNS.fetch_add(1, Ordering::SeqCst) > 0,
+        }
+    }
+
+    /// Whether another run was active at any point during this one
+    fn overlapped(&self) -> bool {
+        self.joined_others || STARTED_RUNS.load(Ordering::SeqCst) > self.number + 1
+    }
+}
+
+impl Drop for ActiveRun {
+    fn drop(&mut self) {
+        ACTIVE_RUNS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Collects what a run needs for its history entry from before it is
+/// submitted. The file lists come from the changes rclone logs, which are
+/// complete but shared by every job running at the time. When runs
+/// overlap, they come from the files rclone reports for the run's own job
+/// instead, so runs don't get each other's changes. rclone only keeps the
+/// most recent of those, so very large overlapping runs can miss files.
+pub(super) struct RunRecorder {
+    origin: RunOrigin,
+    source: String,
+    destination: String,
+    sync_mode: bool,
+    started_at: DateTime<Local>,
+    log_offset: u64,
+    backup_path: Option<String>,
+    active: ActiveRun,
+}
+
+impl RunRecorder {
+    pub async fn start(app: &AppHandle, origin: RunOrigin, config: &DownloadConfig) -> Self {
+        Self {
+            origin,
+            source: config.source().to_string(),
+            destination: config
+                .build_destination_path()
+                .to_string_lossy()
+                .to_string(),
+            sync_mode: config.sync_mode(),
+            started_at: Local::now(),
+            log_offset: LogManager::get_current_offset(app).await,
+            backup_path: None,
+            active: ActiveRun::start(),
+        }
+    }
+
+    pub fn set_backup_path(&mut self, backup_path: Option<String>) {
+        self.backup_path = backup_path;
+    }
+
+    /// Add the run to the history, notify the frontend and return the entry.
+    /// `transferred` holds the files the run's job was done with.
+    pub async fn finish(
+        self,
+        app: &AppHandle,
+        result: &Result<SyncJobResult, AppError>,
+        transferred: &[TransferredFile],
+    ) -> SyncRun {
+        let logged = LogManager::parse_sync_actions(app, self.log_offset)
+            .await
+            .unwrap_or_default();
+        let actions = if self.active.overlapped() {
+            self.job_actions(transferred, &logged)
+        } else {
+            logged
+        };
+        let run = self.build(result, actions, Local::now());
+
+        let saved = match get_history_path(app) {
+            Ok(path) => append_run(&path, &run).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = saved {
+            crate::error::report_background_error(app, "Failed to record sync run", e);
+        }
+        let _ = app.emit("sync-run-recorded", &run.summary);
+        run
+    }
+
+    /// Whether a file system reported by rclone is the archive or its backup
+    /// folder, and not some other download's destination
+    fn writes_to(&self, dst_fs: &str) -> bool {
+        let dst_fs = normalize_fs(dst_fs);
+        normalize_fs(&self.destination) == dst_fs
+            || self
+                .backup_path
+                .as_deref()
+                .is_some_and(|backup| normalize_fs(backup) == dst_fs)
+    }
+
+    fn is_backup(&self, dst_fs: Option<&str>) -> bool {
+        match (self.backup_path.as_deref(), dst_fs) {
+            (Some(backup), Some(dst_fs)) => normalize_fs(backup) == normalize_fs(dst_fs),
+            // Without the file system, a move counts as a backup whenever
+            // a backup folder is set, as rclone only moves files out then
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// The changes of the run's own job, from the files rclone reports for
+    /// it. The log is only read to tell replaced files from new ones.
+    fn job_actions(
+        &self,
+        transferred: &[TransferredFile],
+        logged: &[SyncAction],
+    ) -> Vec<SyncAction> {
+        let replaced: HashSet<&str> = logged
+            .iter()
+            .filter_map(|action| match action {
+                SyncAction::Copied {
+                    path,
+                    replaced: true,
+                } => Some(path.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        transferred
+            .iter()
+            .filter(|file| file.dst_fs.as_deref().is_none_or(|fs| self.writes_to(fs)))
+            .filter_map(|file| {
+                if let Some(reason) = &file.error {
+                    return Some(SyncAction::Failed {
+                        path: Some(file.name.clone()),
+                        reason: reason.clone(),
+                    });
+                }
+                match file.what.as_str() {
+                    "deleting" => Some(SyncAction::Deleted {
+                        path: file.name.clone(),
+                    }),
+                    "moving" | "renaming" if self.is_backup(file.dst_fs.as_deref()) => {
+                        Some(SyncAction::Moved {
+                            from: file.name.clone(),
+                            to: None,
+                        })
+                    }
+                    "moving" | "renaming" => {
+                        let from = file.src_remote.clone().unwrap_or(file.name.clone());
+                        let to = file.dst_remote.clone().filter(|to| *to != from)?;
+                        Some(SyncAction::Renamed { from, to })
+                    }
+                    _ if file.is_copied() => Some(SyncAction::Copied {
+                        path: file.name.clone(),
+                        replaced: replaced.contains(file.name.as_str()),
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn build(
+        self,
+        result: &Result<SyncJobResult, AppError>,
+        actions: Vec<SyncAction>,
+        finished_at: DateTime<Local>,
+    ) -> SyncRun {
+        let (status, error) = match result {
+            Ok(stats) if stats.errors > 0 => (SyncRunStatus::PartiallyFailed, None),
+            Ok(_) => (SyncRunStatus::Completed, None),
+            Err(e @ AppError::Cancelled(_)) => (SyncRunStatus::Cancelled, Some(e)),
+            Err(e @ AppError::Interrupted) => (SyncRunStatus::Interrupted, Some(e)),
+            Err(e) => (SyncRunStatus::Failed, Some(e)),
+        };
+        let stats = result.as_ref().cloned().unwrap_or_default();
+
+        let mut run = SyncRun {
+            summary: SyncRunSummary {
+                id: format!(
+                    "{}-{:08x}",
+                    self.started_at.format("%Y%m%d%H%M%S%3f"),
+                    rand::random::<u32>()
+                ),
+                origin: self.origin,
+                source: self.source,
+                destination: self.destination,
+                sync_mode: self.sync_mode,
+                started_at: self.started_at.to_rfc3339(),
+                finished_at: finished_at.to_rfc3339(),
+                status,
+                error: error.map(|e| e.to_string()),
+                error_code: error.map(|e| e.code().to_string()),
+                transfers: stats.transfers,
+                checks: stats.checks,
+                deletes: stats.deletes,
+                renames: stats.renames,
+                bytes: stats.bytes,
+                errors: stats.errors,
+                backup_path: self.backup_path,
+            },
+            copied: Vec::new(),
+            replaced: Vec::new(),
+            deleted: Vec::new(),
+            backed_up: Vec::new(),
+            renamed: Vec::new(),
+            failed: Vec::new(),
+        };
+
+        let mut copied = Vec::new();
+        for action in actions {
+            match action {
+                SyncAction::Copied { path, replaced } => copied.push((path, replaced)),
+                SyncAction::Deleted { path } => run.deleted.push(path),
+                // Moves out of the archive when a backup folder is set
+                SyncAction::Moved { from, .. } if run.summary.backup_path.is_some() => {
+                    run.backed_up.push(from)
+                }
+                SyncAction::Moved { from, to: Some(to) } | SyncAction::Renamed { from, to } => {
+                    run.renamed.push(RenamedFile { from, to })
+                }
+                SyncAction::Moved { .. } => {}
+                SyncAction::Failed { path, reason } => run.failed.push(FailedFile { path, reason }),
+            }
+        }
+        // A file is replaced when rclone says so or its old version went
+        // to the backup folder
+        let backed_up: HashSet<&String> = run.backed_up.iter().collect();
+        for (path, replaced) in copied {
+            if replaced || backed_up.contains(&path) {
+                run.replaced.push(path);
+            } else {
+                run.copied.push(path);
+            }
+        }
+
+        run
+    }
+}
+
+/// Compare file systems the way rclone reports them, whatever the slashes
+/// or the Windows long path prefix
+fn normalize_fs(fs: &str) -> String {
+    let fs = fs.replace('\\', "/");
+    let fs = fs.strip_prefix("//?/").unwrap_or(&fs);
+    fs.trim_end_matches('/').to_string()
+}
+
+fn get_history_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| AppError::Internal(format!("Failed to get app data dir: {}", e)))?
+        .join("history.jsonl"))
+}
+
+async fn append_run(path: &Path, run: &SyncRun) -> Result<(), AppError> {
+    let mut line = serde_json::to_string(run)?;
+    line.push('\n');
+
+    let _guard = HISTORY_LOCK.lock().await;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| AppError::io("Failed to create app data dir", e))?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| AppError::io("Failed to open sync history", e))?;
+    file.write_all(line.as_bytes())
+        .await
+        .map_err(|e| AppError::io("Failed to write sync history", e))
+}
+
+/// Read every recorded run, oldest first. Lines that can't be parsed, e.g.
+/// one cut short by a crash, are skipped.
+async fn read_runs(path: &Path) -> Result<Vec<SyncRun>, AppError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| AppError::io("Failed to read sync history", e))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Every change to files whose path contains the search term, oldest first
+fn find_file_events(runs: &[SyncRun], search: &str) -> Vec<FileEvent> {
+    let search = search.trim().to_lowercase();
+    let mut events = Vec::new();
+
+    for run in runs {
+        let mut add = |path: &str, kind: FileEventKind| {
+            if path.to_lowercase().contains(&search) {
+                events.push(FileEvent {
+                    run_id: run.summary.id.clone(),
+                    time: run.summary.finished_at.clone(),
+                    path: path.to_string(),
+                    kind,
+                });
+            }
+        };
+
+        for path in &run.copied {
+            add(path, FileEventKind::Added);
+        }
+        for path in &run.replaced {
+            add(path, FileEventKind::Replaced);
+        }
+        for path in &run.deleted {
+            add(path, FileEventKind::Deleted);
+        }
+        for path in &run.backed_up {
+            add(path, FileEventKind::BackedUp);
+        }
+        for renamed in &run.renamed {
+            add(&renamed.from, FileEventKind::RenamedFrom);
+            add(&renamed.to, FileEventKind::RenamedTo);
+        }
+        for failed in &run.failed {
+            if let Some(path) = &failed.path {
+                add(path, FileEventKind::Failed);
+            }
+        }
+    }
+
+    events
+}
+
+/// List recorded runs, newest first
+#[tauri::command]
+pub async fn list_sync_runs(
+    app: AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<SyncRunSummary>, AppError> {
+    let runs = read_runs(&get_history_path(&app)?).await?;
+    Ok(runs
+        .into_iter()
+        .rev()
+        .take(limit.unwrap_or(DEFAULT_LIST_LIMIT))
+        .map(|run| run.summary)
+        .collect())
+}
+
+/// Get a recorded run with its file lists
+#[tauri::command]
+pub async fn get_sync_run(app: AppHandle, id: String) -> Result<SyncRun, AppError> {
+    read_runs(&get_history_path(&app)?)
+        .await?
+        .into_iter()
+        .find(|run| run.summary.id == id)
+        .ok_or(AppError::NotFound(format!("Sync run {}", id)))
+}
+
+/// Find when files matching a search term were added, changed or removed
+#[tauri::command]
+pub async fn find_file_history(app: AppHandle, search: String) -> Result<Vec<FileEvent>, AppError> {
+    if search.trim().is_empty() {
+        return Err(AppError::InvalidRequest(
+            "Search term is required".to_string(),
+        ));
+    }
+    let runs = read_runs(&get_history_path(&app)?).await?;
+    Ok(find_file_events(&runs, &search))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorder(backup_path: Option<&str>) -> RunRecorder {
+        RunRecorder {
+            origin: RunOrigin::Schedule {
+                profile_id: 3,
+                profile_name: "Nightly".to_string(),
+            },
+            source: "FOLDER_ID".to_string(),
+            destination: ARCHIVE.to_string(),
+            sync_mode: true,
+            started_at: Local::now(),
+            log_offset: 0,
+            backup_path: backup_path.map(str::to_string),
+            active: ActiveRun::start(),
+        }
+    }
+
+    const ARCHIVE: &str = "/music/An-Unofficial-Neuro-Karaoke-Archive";
+    const BACKUP: &str = "/music/Backup-KAR-20260102_030405";
+
+    fn file(what: &str, name: &str, dst_fs: &str) -> TransferredFile {
+        TransferredFile {
+            name: name.to_string(),
+            what: what.to_string(),
+            dst_fs: Some(dst_fs.to_string()),
+            src_remote: Some(name.to_string()),
+            dst_remote: Some(name.to_string()),
+            error: None,
+            completed_at: None,
+        }
+    }
+
+    fn transferred() -> Vec<TransferredFile> {
+        vec![
+            file("transferring", "album/new.mp3", ARCHIVE),
+            file("moving", "album/old.mp3", BACKUP),
+            file("transferring", "album/old.mp3", ARCHIVE),
+            file("deleting", "album/gone.mp3", ARCHIVE),
+            TransferredFile {
+                dst_remote: Some("album/after.mp3".to_string()),
+                ..file("moving", "album/before.mp3", ARCHIVE)
+            },
+            TransferredFile {
+                error: Some("quota exceeded".to_string()),
+                ..file("transferring", "album/big.flac", ARCHIVE)
+            },
+            // Another download running at the same time
+            file("transferring", "other.mp3", "/elsewhere"),
+        ]
+    }
+
+    #[test]
+    fn run_lists_every_change() {
+        let stats = SyncJobResult {
+            transfers: 2,
+            bytes: 100,
+            ..Default::default()
+        };
+        let with_backup = recorder(Some(BACKUP));
+        let actions = with_backup.job_actions(&transferred(), &[]);
+        let run = with_backup.build(&Ok(stats), actions, Local::now());
+
+        assert_eq!(run.summary.status, SyncRunStatus::Completed);
+        assert_eq!((run.summary.transfers, run.summary.bytes), (2, 100));
+        assert_eq!(run.copied, ["album/new.mp3"]);
+        assert_eq!(run.replaced, ["album/old.mp3"]);
+        assert_eq!(run.deleted, ["album/gone.mp3"]);
+        assert_eq!(run.backed_up, ["album/old.mp3"]);
+        assert_eq!(
+            run.renamed,
+            [RenamedFile {
+                from: "album/before.mp3".to_string(),
+                to: "album/after.mp3".to_string(),
+            }]
+        );
+        assert_eq!(
+            run.failed,
+            [FailedFile {
+                path: Some("album/big.flac".to_string()),
+                reason: "quota exceeded".to_string(),
+            }]
+        );
+
+        let stats = SyncJobResult {
+            errors: 1,
+            ..Default::default()
+        };
+        let run = recorder(None).build(&Ok(stats), Vec::new(), Local::now());
+        assert_eq!(run.summary.status, SyncRunStatus::PartiallyFailed);
+        assert_eq!(run.summary.error, None);
+
+        // Without a backup folder only the log tells replaced files apart,
+        // and moves to the other folder belong to another download
+        let logged = [SyncAction::Copied {
+            path: "album/old.mp3".to_string(),
+            replaced: true,
+        }];
+        let without_backup = recorder(None);
+        let actions = without_backup.job_actions(&transferred(), &logged);
+        let run = without_backup.build(
+            &Err(AppError::QuotaExceeded("quota".to_string())),
+            actions,
+            Local::now(),
+        );
+        assert_eq!(run.summary.status, SyncRunStatus::Failed);
+        assert_eq!(run.summary.error_code.as_deref(), Some("quota_exceeded"));
+        assert!(run.backed_up.is_empty());
+        assert_eq!(run.copied, ["album/new.mp3"]);
+        assert_eq!(run.replaced, ["album/old.mp3"]);
+        assert_eq!(run.renamed.len(), 1);
+    }
+
+    #[test]
+    fn logged_changes_are_all_recorded() {
+        // Far more files than rclone keeps in a job's transferred list
+        let mut logged: Vec<SyncAction> = (0..250)
+            .map(|i| SyncAction::Copied {
+                path: format!("album/{:03}.mp3", i),
+                replaced: false,
+            })
+            .collect();
+        logged.push(SyncAction::Moved {
+            from: "album/007.mp3".to_string(),
+            to: None,
+        });
+        logged.push(SyncAction::Deleted {
+            path: "album/gone.mp3".to_string(),
+        });
+
+        let run = recorder(Some(BACKUP)).build(&Ok(SyncJobResult::default()), logged, Local::now());
+        assert_eq!(run.copied.len(), 249);
+        assert_eq!(run.copied[249 - 1], "album/249.mp3");
+        assert_eq!(run.replaced, ["album/007.mp3"]);
+        assert_eq!(run.backed_up, ["album/007.mp3"]);
+        assert_eq!(run.deleted, ["album/gone.mp3"]);
+    }
+
+    #[test]
+    fn file_systems_match_across_path_styles() {
+        let recorder = recorder(Some("C:\\Music\\Backup-KAR-20260102_030405"));
+        assert!(recorder.writes_to("//?/C:/Music/Backup-KAR-20260102_030405/"));
+        assert!(recorder.writes_to(&format!("{}/", ARCHIVE)));
+        assert!(!recorder.writes_to("/music/Other"));
+    }
+
+    #[tokio::test]
+    async fn runs_are_appended_and_searched() {
+        let path = std::env::temp_dir().join(format!("history-{}.jsonl", std::process::id()));
+        let _ = tokio::fs::remove_file(&path).await;
+
+        let first_recorder = recorder(None);
+        let actions = first_recorder.job_actions(&transferred(), &[]);
+        let first = first_recorder.build(&Ok(SyncJobResult::default()), actions, Local::now());
+        let second = recorder(None).build(
+            &Err(AppError::Cancelled("Download cancelled".to_string())),
+            vec![SyncAction::Deleted {
+                path: "album/New.mp3".to_string(),
+            }],
+            Local::now(),
+        );
+        append_run(&path, &first).await.unwrap();
+        append_run(&path, &second).await.unwrap();
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await
+            .unwrap()
+            .write_all(b"{\"id\":")
+            .await
+            .unwrap();
+
+        let runs = read_runs(&path).await.unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[1].summary.status, SyncRunStatus::Cancelled);
+        assert_eq!(runs[0].summary.origin, first.summary.origin);
+
+        let events = find_file_events(&runs, "new.mp3");
+        let kinds: Vec<(String, FileEventKind)> = events
+            .into_iter()
+            .map(|event| (event.run_id, event.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (first.summary.id.clone(), FileEventKind::Added),
+                (second.summary.id.clone(), FileEventKind::Deleted),
+            ]
+        );
+    }
+}
@@ -1,21 +1,192 @@
-use super::types::GdriveFile;
+use super::manifest;
+use super::types::{self, FolderEntry, FolderPage, GdriveFile, GdriveListing};
 use super::utils::parse_gdrive_id;
 use crate::api::rclone;
+use crate::error::AppError;
+use std::collections::HashMap;
 
+/// Number of entries in a page of a folder listing when no limit is given
+const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// List every file below a Drive folder. A cached listing is returned right
+/// away and refreshed in the background, emitting `gdrive-files-changed`
+/// when the refresh finds changes.
 #[tauri::command]
 pub async fn list_gdrive_files(
     app: tauri::AppHandle,
     source: String,
     remote_config: String,
-) -> Result<Vec<GdriveFile>, String> {
+) -> Result<GdriveListing, AppError> {
+    let (loaded, refreshing) = manifest::load(&app, &source, &remote_config).await?;
+    Ok(GdriveListing {
+        files: build_tree(&loaded.manifest.entries),
+        refreshed_at: loaded.manifest.refreshed_at.clone(),
+        refreshing,
+    })
+}
+
+/// List every file below a Drive folder again, skipping the cache
+#[tauri::command]
+pub async fn refresh_gdrive_files(
+    app: tauri::AppHandle,
+    source: String,
+    remote_config: String,
+) -> Result<GdriveListing, AppError> {
+    let loaded = manifest::refresh(&app, &source, &remote_config).await?;
+    Ok(GdriveListing {
+        files: build_tree(&loaded.manifest.entries),
+        refreshed_at: loaded.manifest.refreshed_at.clone(),
+        refreshing: false,
+    })
+}
+
+/// List a page of the files and folders directly inside a folder of the
+/// archive, folders first. Folder sizes and child counts come from the
+/// folder index of the cached listing when there is one, otherwise only the
+/// folder itself is listed on Drive, while the full listing is fetched in
+/// the background.
+#[tauri::command]
+pub async fn list_gdrive_folder(
+    app: tauri::AppHandle,
+    source: String,
+    remote_config: String,
+    path: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<FolderPage, AppError> {
+    let path = path.trim_matches('/').to_string();
+    let (cached, _) = manifest::cached(&app, &source, &remote_config).await?;
+
+    if let Some(loaded) = cached {
+        let entries = loaded.folders().get(&path).map_or(&[][..], Vec::as_slice);
+        let refreshed_at = Some(loaded.manifest.refreshed_at.clone());
+        return Ok(page(path, entries, offset, limit, refreshed_at));
+    }
+
     let client = rclone::get_sdk_client(&app).await?;
-    let root_id = parse_gdrive_id(&source);
+    let entries = list_folder(&client, &source, &remote_config, &path).await?;
+    Ok(page(path, &entries, offset, limit, None))
+}
+
+fn page(
+    path: String,
+    entries: &[FolderEntry],
+    offset: Option<usize>,
+    limit: Option<usize>,
+    refreshed_at: Option<String>,
+) -> FolderPage {
+    let offset = offset.unwrap_or(0);
+    let total = entries.len();
+    FolderPage {
+        path,
+        entries: entries
+            .iter()
+            .skip(offset)
+            .take(limit.unwrap_or(DEFAULT_PAGE_SIZE))
+            .cloned()
+            .collect(),
+        total,
+        offset,
+        refreshed_at,
+    }
+}
+
+/// The folder a path is in, empty for the root
+fn parent_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+/// The entries directly inside each folder of a full listing, keyed by
+/// folder path, with the child count and total size of each subfolder
+pub(super) fn folder_index(entries: &[GdriveFile]) -> HashMap<String, Vec<FolderEntry>> {
+    let tree = build_tree(entries);
+
+    let mut stats: HashMap<String, (usize, i64)> = HashMap::new();
+    for file in &tree {
+        stats
+            .entry(parent_of(&file.path).to_string())
+            .or_default()
+            .0 += 1;
+        if !file.is_dir {
+            let mut ancestor = file.path.as_str();
+            while let Some((parent, _)) = ancestor.rsplit_once('/') {
+                stats.entry(parent.to_string()).or_default().1 += file.size;
+                ancestor = parent;
+            }
+        }
+    }
+
+    let mut folders: HashMap<String, Vec<FolderEntry>> = HashMap::new();
+    for file in tree {
+        let (child_count, total_size) = if file.is_dir {
+            let (count, size) = stats.get(&file.path).copied().unwrap_or_default();
+            (Some(count), Some(size))
+        } else {
+            (None, None)
+        };
+        folders
+            .entry(parent_of(&file.path).to_string())
+            .or_default()
+            .push(FolderEntry {
+                file,
+                child_count,
+                total_size,
+            });
+    }
+    folders
+}
+
+/// List the files and folders directly inside a folder of the archive,
+/// folders first, without their sizes or child counts
+async fn list_folder(
+    client: &rclone_sdk::Client,
+    source: &str,
+    remote_config: &str,
+    folder: &str,
+) -> Result<Vec<FolderEntry>, AppError> {
+    let mut files = list_folder_entries(client, source, remote_config, folder, false).await?;
+    // Paths are relative to the archive, so the folder itself or anything
+    // deeper never belongs in its page
+    files.retain(|file| file.path != folder && parent_of(&file.path) == folder);
+    sort_folders_first(&mut files);
+    Ok(files
+        .into_iter()
+        .map(|file| FolderEntry {
+            file,
+            child_count: None,
+            total_size: None,
+        })
+        .collect())
+}
+
+/// List every file and folder below a Drive folder as Drive reports them
+pub(super) async fn list_entries(
+    client: &rclone_sdk::Client,
+    source: &str,
+    remote_config: &str,
+) -> Result<Vec<GdriveFile>, AppError> {
+    list_folder_entries(client, source, remote_config, "", true).await
+}
+
+/// List the files and folders in a folder of the archive, or everything
+/// below it when recursing
+async fn list_folder_entries(
+    client: &rclone_sdk::Client,
+    source: &str,
+    remote_config: &str,
+    folder: &str,
+    recurse: bool,
+) -> Result<Vec<GdriveFile>, AppError> {
+    let root_id = parse_gdrive_id(source);
 
     // Construct fs pointing to the root of the share/folder
     let fs_str = format!("{},root_folder_id={}:", remote_config, root_id);
 
     let opt_json = serde_json::json!({
-        "recurse": true
+        "recurse": recurse,
+        "showHash": true,
+        "showOrigIDs": true,
+        "metadata": true
     })
     .to_string();
 
@@ -32,30 +203,56 @@ pub async fn list_gdrive_files(
             None,
             Some(&opt_json), // opt
             None,            // recurse (does not show files, use opt)
-            "",              // remote
+            folder,          // remote
             None,
             None,
             None,
         )
         .await
-        .map_err(|e| format!("List failed: {}", e))?;
-
-    // Extract list
-    let list = result.into_inner().list;
-
-    let mut files = Vec::new();
+        .map_err(|e| match AppError::from(e) {
+            // The folder id doesn't exist or isn't shared with this account
+            AppError::NotFound(message) => AppError::InvalidSource(message),
+            e => e,
+        })?;
 
-    if let Some(entries) = Some(list) {
-        for item in entries {
-            files.push(GdriveFile {
+    Ok(result
+        .into_inner()
+        .list
+        .into_iter()
+        .map(|item| {
+            let size = item.size.map(|s| s as i64).unwrap_or(0);
+            let mime_type = item.mime_type.unwrap_or_default();
+            let (shortcut_id, id) = match item.orig_id.or(item.id) {
+                Some(id) => {
+                    let (shortcut_id, id) = types::split_shortcut_id(&id);
+                    (shortcut_id, Some(id))
+                }
+                None => (None, None),
+            };
+            GdriveFile {
+                is_google_doc: types::is_google_doc(&mime_type, size, item.is_dir),
                 path: item.path,
                 name: item.name,
                 is_dir: item.is_dir,
-                size: item.size.map(|s| s as i64).unwrap_or(0),
-                mime_type: item.mime_type.unwrap_or_default(),
-            });
-        }
-    }
+                size,
+                mime_type,
+                mod_time: item.mod_time,
+                created_time: item
+                    .metadata
+                    .get("btime")
+                    .and_then(|time| time.as_str())
+                    .map(str::to_string),
+                hashes: item.hashes.into_iter().collect(),
+                id,
+                shortcut_id,
+            }
+        })
+        .collect())
+}
+
+/// Turn listed entries into the browser's file tree, folders first
+pub(super) fn build_tree(entries: &[GdriveFile]) -> Vec<GdriveFile> {
+    let mut files = entries.to_vec();
 
     // Synthesize missing parent directories
     let existing_paths: std::collections::HashSet<String> =
@@ -74,6 +271,7 @@ pub async fn list_gdrive_files(
                     is_dir: true,
                     size: 0,
                     mime_type: "inode/directory".to_string(),
+                    ..Default::default()
                 });
                 added_paths.insert(parent_path);
             }
@@ -81,13 +279,191 @@ pub async fn list_gdrive_files(
     }
 
     files.extend(new_dirs);
+    sort_folders_first(&mut files);
+    files
+}
 
-    // Sort: Folders first, then alphabetical
+/// Sort folders first, then alphabetically
+fn sort_folders_first(files: &mut [GdriveFile]) {
     files.sort_by(|a, b| match (a.is_dir, b.is_dir) {
         (true, false) => std::cmp::Ordering::Less,
         (false, true) => std::cmp::Ordering::Greater,
         _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
     });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rclone::mock::MockRcServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn listing_adds_missing_folders_and_sorts_folders_first() {
+        let server = MockRcServer::start().await;
+        server.state().files = vec![
+            json!({"Path": "b.mp3", "Name": "b.mp3", "IsDir": false, "Size": 10.0, "MimeType": "audio/mpeg"}),
+            json!({"Path": "Album/Disc 1/a.mp3", "Name": "a.mp3", "IsDir": false, "Size": 5.0}),
+            json!({"Path": "A.txt", "Name": "A.txt", "IsDir": false}),
+        ];
+
+        let entries = list_entries(&server.client(), "FOLDER_ID", "gdrive")
+            .await
+            .unwrap();
+        let files = build_tree(&entries);
+
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "Album",
+                "Album/Disc 1",
+                "Album/Disc 1/a.mp3",
+                "A.txt",
+                "b.mp3"
+            ]
+        );
+        assert!(files[0].is_dir);
+        assert_eq!(files[0].mime_type, "inode/directory");
+        assert_eq!(files[4].size, 10);
+
+        let calls = &server.state().calls;
+        assert_eq!(calls[0].0, "operations/list");
+        assert_eq!(calls[0].1["fs"], "gdrive,root_folder_id=FOLDER_ID:");
+    }
+
+    #[tokio::test]
+    async fn listing_keeps_dates_hashes_and_ids() {
+        let server = MockRcServer::start().await;
+        server.state().files = vec![
+            json!({
+                "Path": "song.mp3", "Name": "song.mp3", "IsDir": false, "Size": 10.0,
+                "ModTime": "2026-01-02T03:04:05Z", "ID": "FILE_ID",
+                "Hashes": {"md5": "abc", "sha1": "def"},
+                "Metadata": {"btime": "2025-12-31T00:00:00Z"},
+            }),
+            json!({
+                "Path": "link.mp3", "Name": "link.mp3", "IsDir": false, "Size": 10.0,
+                "ID": "TARGET_ID\tSHORTCUT_ID",
+            }),
+            json!({
+                "Path": "notes.docx", "Name": "notes.docx", "IsDir": false, "Size": -1.0,
+                "MimeType": "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            }),
+        ];
+
+        let entries = list_entries(&server.client(), "FOLDER_ID", "gdrive")
+            .await
+            .unwrap();
+
+        assert_eq!(entries[0].mod_time.as_deref(), Some("2026-01-02T03:04:05Z"));
+        assert_eq!(
+            entries[0].created_time.as_deref(),
+            Some("2025-12-31T00:00:00Z")
+        );
+        assert_eq!(entries[0].md5(), Some("abc"));
+        assert_eq!(entries[0].hashes["sha1"], "def");
+        assert_eq!(entries[0].id.as_deref(), Some("FILE_ID"));
+        assert!(!entries[0].is_shortcut() && !entries[0].is_google_doc);
+
+        assert_eq!(entries[1].id.as_deref(), Some("TARGET_ID"));
+        assert_eq!(entries[1].shortcut_id.as_deref(), Some("SHORTCUT_ID"));
+        assert!(entries[2].is_google_doc);
+
+        let opt: serde_json::Value =
+            serde_json::from_str(server.state().calls[0].1["opt"].as_str().unwrap()).unwrap();
+        assert_eq!(opt["showHash"], true);
+        assert_eq!(opt["showOrigIDs"], true);
+        assert_eq!(opt["metadata"], true);
+    }
 
-    Ok(files)
+    #[tokio::test]
+    async fn missing_folder_is_an_invalid_source() {
+        let server = MockRcServer::start().await;
+        server.state().list_error = Some("directory not found".to_string());
+
+        let result = list_entries(&server.client(), "MISSING", "gdrive").await;
+
+        assert!(matches!(result, Err(AppError::InvalidSource(_))));
+    }
+
+    fn entry(path: &str, is_dir: bool, size: i64) -> GdriveFile {
+        GdriveFile {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap().to_string(),
+            is_dir,
+            size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn folders_from_the_manifest_count_their_contents() {
+        let entries = vec![
+            entry("Album", true, 0),
+            entry("Album/a.mp3", false, 10),
+            entry("Album/Disc 2/b.mp3", false, 5),
+            entry("c.mp3", false, 1),
+        ];
+
+        let folders = folder_index(&entries);
+        let (root, album) = (&folders[""], &folders["Album"]);
+
+        let paths: Vec<&str> = root.iter().map(|e| e.file.path.as_str()).collect();
+        assert_eq!(paths, ["Album", "c.mp3"]);
+        assert_eq!(
+            (root[0].child_count, root[0].total_size),
+            (Some(2), Some(15))
+        );
+        assert_eq!((root[1].child_count, root[1].total_size), (None, None));
+
+        let paths: Vec<&str> = album.iter().map(|e| e.file.path.as_str()).collect();
+        assert_eq!(paths, ["Album/Disc 2", "Album/a.mp3"]);
+        assert_eq!(
+            (album[0].child_count, album[0].total_size),
+            (Some(1), Some(5))
+        );
+    }
+
+    #[test]
+    fn folders_are_paged() {
+        let entries = (0..5)
+            .map(|i| entry(&format!("{}.mp3", i), false, 1))
+            .collect::<Vec<_>>();
+        let folders = folder_index(&entries);
+
+        let page = page(String::new(), &folders[""], Some(3), Some(10), None);
+
+        assert_eq!(page.total, 5);
+        assert_eq!(page.offset, 3);
+        let paths: Vec<&str> = page.entries.iter().map(|e| e.file.path.as_str()).collect();
+        assert_eq!(paths, ["3.mp3", "4.mp3"]);
+    }
+
+    #[tokio::test]
+    async fn single_folder_is_listed_without_recursing() {
+        let server = MockRcServer::start().await;
+        // rclone lists the folder's entries with paths from the archive root
+        server.state().files = vec![
+            json!({"Path": "Album/b.mp3", "Name": "b.mp3", "IsDir": false, "Size": 10.0}),
+            json!({"Path": "Album/Disc 2", "Name": "Disc 2", "IsDir": true, "Size": -1.0}),
+            json!({"Path": "Album/A.mp3", "Name": "A.mp3", "IsDir": false, "Size": 5.0}),
+        ];
+
+        let entries = list_folder(&server.client(), "FOLDER_ID", "gdrive", "Album")
+            .await
+            .unwrap();
+
+        let paths: Vec<&str> = entries.iter().map(|e| e.file.path.as_str()).collect();
+        assert_eq!(paths, ["Album/Disc 2", "Album/A.mp3", "Album/b.mp3"]);
+        assert!(entries[0].file.is_dir);
+        assert_eq!(entries[2].file.size, 10);
+        assert!(entries.iter().all(|e| e.child_count.is_none()));
+
+        let calls = &server.state().calls;
+        assert_eq!(calls[0].1["remote"], "Album");
+        let opt: serde_json::Value =
+            serde_json::from_str(calls[0].1["opt"].as_str().unwrap()).unwrap();
+        assert_eq!(opt["recurse"], false);
+    }
 }
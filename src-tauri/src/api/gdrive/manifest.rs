@@ -0,0 +1,336 @@
+use super::list;
+use super::types::{FolderEntry, GdriveFile};
+use super::utils::{parse_gdrive_id, write_atomic};
+use crate::api::rclone;
+use crate::error::AppError;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, OnceLock};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+/// Minimum age of a cached listing before it is refreshed in the background.
+/// The archive changes a few times a day at most, and the listing can be
+/// refreshed on demand.
+const REFRESH_AFTER: chrono::Duration = chrono::Duration::hours(6);
+
+/// Manifests being refreshed, so a folder is only listed once at a time
+static REFRESHING: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Manifests read or fetched since the app started, keyed by their path on
+/// disk, so a listing isn't parsed again for every request
+static LOADED: LazyLock<Mutex<HashMap<PathBuf, Arc<LoadedManifest>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The last listing of a Drive folder. Listing the whole archive takes long
+/// and counts against the Drive quota, so it is kept on disk and refreshed
+/// in the background by listing the folder again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub source: String,
+    pub remote_config: String,
+    pub refreshed_at: String,
+    pub entries: Vec<GdriveFile>,
+}
+
+/// A manifest kept in memory, with the indexes built from it on first use.
+/// A refresh replaces it rather than changing it.
+pub(super) struct LoadedManifest {
+    pub manifest: Manifest,
+    folders: OnceLock<HashMap<String, Vec<FolderEntry>>>,
+}
+
+impl LoadedManifest {
+    /// The entries directly inside each folder, keyed by folder path
+    pub fn folders(&self) -> &HashMap<String, Vec<FolderEntry>> {
+        self.folders
+            .get_or_init(|| list::folder_index(&self.manifest.entries))
+    }
+}
+
+/// Sent with `gdrive-files-changed` when a refresh finds a different listing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilesChanged {
+    source: String,
+    remote_config: String,
+    refreshed_at: String,
+}
+
+fn get_manifest_path(
+    app: &AppHandle,
+    source: &str,
+    remote_config: &str,
+) -> Result<PathBuf, AppError> {
+    // Keep the name safe for any filesystem
+    let name: String = format!("{}-{}", remote_config, parse_gdrive_id(source))
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    Ok(app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| AppError::Internal(format!("Failed to get app data dir: {}", e)))?
+        .join("manifests")
+        .join(format!("{}.json", name)))
+}
+
+/// Read a cached manifest. One that can't be read is treated as missing and
+/// fetched again.
+async fn read_manifest(path: &Path) -> Option<Manifest> {
+    let content = tokio::fs::read(path).await.ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+/// Get a manifest from memory, reading it from disk the first time
+async fn read_loaded(path: &Path) -> Option<Arc<LoadedManifest>> {
+    // Held while reading, so a fetch finishing meanwhile isn't overwritten
+    let mut loaded = LOADED.lock().await;
+    if let Some(manifest) = loaded.get(path) {
+        return Some(manifest.clone());
+    }
+    let manifest = Arc::new(LoadedManifest {
+        manifest: read_manifest(path).await?,
+        folders: OnceLock::new(),
+    });
+    loaded.insert(path.to_path_buf(), manifest.clone());
+    Some(manifest)
+}
+
+/// Write a manifest to a temporary file first and move it in place, so a
+/// crash or a concurrent read never sees half of it
+async fn write_manifest(path: &Path, manifest: &Manifest) -> Result<(), AppError> {
+    let content = serde_json::to_vec(manifest)?;
+    write_atomic(path, &content, "manifest").await
+}
+
+/// Whether a cached listing is old enough to be refreshed
+fn is_stale(manifest: &Manifest, now: DateTime<Local>) -> bool {
+    DateTime::parse_from_rfc3339(&manifest.refreshed_at)
+        .map(|refreshed_at| now.signed_duration_since(refreshed_at) >= REFRESH_AFTER)
+        .unwrap_or(true)
+}
+
+/// Whether two listings hold different files, regardless of their order
+fn has_changed(old: &[GdriveFile], new: &[GdriveFile]) -> bool {
+    if old.len() != new.len() {
+        return true;
+    }
+    let mut old: Vec<&GdriveFile> = old.iter().collect();
+    let mut new: Vec<&GdriveFile> = new.iter().collect();
+    old.sort_by(|a, b| a.path.cmp(&b.path));
+    new.sort_by(|a, b| a.path.cmp(&b.path));
+    old != new
+}
+
+/// List a folder on Drive and cache the listing, notifying the frontend
+/// when it differs from the cached one
+async fn fetch(
+    app: &AppHandle,
+    path: &Path,
+    source: &str,
+    remote_config: &str,
+    previous: Option<&[GdriveFile]>,
+) -> Result<Arc<LoadedManifest>, AppError> {
+    let client = rclone::get_sdk_client(app).await?;
+    let manifest = Manifest {
+        source: source.to_string(),
+        remote_config: remote_config.to_string(),
+        refreshed_at: Local::now().to_rfc3339(),
+        entries: list::list_entries(&client, source, remote_config).await?,
+    };
+    write_manifest(path, &manifest).await?;
+    let manifest = Arc::new(LoadedManifest {
+        manifest,
+        folders: OnceLock::new(),
+    });
+    LOADED
+        .lock()
+        .await
+        .insert(path.to_path_buf(), manifest.clone());
+
+    if previous.is_some_and(|previous| has_changed(previous, &manifest.manifest.entries)) {
+        let _ = app.emit(
+            "gdrive-files-changed",
+            FilesChanged {
+                source: source.to_string(),
+                remote_config: remote_config.to_string(),
+                refreshed_at: manifest.manifest.refreshed_at.clone(),
+            },
+        );
+    }
+    Ok(manifest)
+}
+
+/// Fetch a listing in the background, unless it is already being fetched.
+/// Returns whether a fetch was started.
+async fn spawn_refresh(
+    app: &AppHandle,
+    path: PathBuf,
+    source: &str,
+    remote_config: &str,
+    previous: Option<Arc<LoadedManifest>>,
+) -> bool {
+    if !REFRESHING.lock().await.insert(path.clone()) {
+        return false;
+    }
+
+    let app = app.clone();
+    let source = source.to_string();
+    let remote_config = remote_config.to_string();
+    tauri::async_runtime::spawn(async move {
+        let previous = previous
+            .as_ref()
+            .map_or(&[][..], |previous| previous.manifest.entries.as_slice());
+        let result = fetch(&app, &path, &source, &remote_config, Some(previous)).await;
+        if let Err(e) = result {
+            crate::error::report_background_error(&app, "Failed to refresh file listing", e);
+        }
+        REFRESHING.lock().await.remove(&path);
+    });
+    true
+}
+
+/// Get the listing of a folder, from the cache when there is one. Returns
+/// whether a refresh was started in the background.
+pub(super) async fn load(
+    app: &AppHandle,
+    source: &str,
+    remote_config: &str,
+) -> Result<(Arc<LoadedManifest>, bool), AppError> {
+    let path = get_manifest_path(app, source, remote_config)?;
+    let Some(cached) = read_loaded(&path).await else {
+        let manifest = fetch(app, &path, source, remote_config, None).await?;
+        return Ok((manifest, false));
+    };
+
+    let refreshing = is_stale(&cached.manifest, Local::now())
+        && spawn_refresh(app, path, source, remote_config, Some(cached.clone())).await;
+    Ok((cached, refreshing))
+}
+
+/// Get the cached listing of a folder without waiting for Drive. A missing
+/// or stale listing is fetched in the background, emitting
+/// `gdrive-files-changed` once it is ready. Returns whether a fetch was
+/// started.
+pub(super) async fn cached(
+    app: &AppHandle,
+    source: &str,
+    remote_config: &str,
+) -> Result<(Option<Arc<LoadedManifest>>, bool), AppError> {
+    let path = get_manifest_path(app, source, remote_config)?;
+    let cached = read_loaded(&path).await;
+
+    let refreshing = match &cached {
+        Some(loaded) if !is_stale(&loaded.manifest, Local::now()) => false,
+        _ => spawn_refresh(app, path, source, remote_config, cached.clone()).await,
+    };
+    Ok((cached, refreshing))
+}
+
+/// List a folder on Drive again, replacing its cached listing
+pub(super) async fn refresh(
+    app: &AppHandle,
+    source: &str,
+    remote_config: &str,
+) -> Result<Arc<LoadedManifest>, AppError> {
+    let path = get_manifest_path(app, source, remote_config)?;
+    let previous = read_loaded(&path).await;
+    let previous = previous
+        .as_ref()
+        .map(|loaded| loaded.manifest.entries.as_slice());
+    fetch(app, &path, source, remote_config, previous).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, size: i64) -> GdriveFile {
+        GdriveFile {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap().to_string(),
+            is_dir: false,
+            size,
+            mime_type: "audio/mpeg".to_string(),
+            mod_time: Some("2026-01-02T03:04:05Z".to_string()),
+            hashes: [("md5".to_string(), format!("{:032x}", size))].into(),
+            id: Some(format!("ID_{}", size)),
+            ..Default::default()
+        }
+    }
+
+    fn manifest(refreshed_at: DateTime<Local>) -> Manifest {
+        Manifest {
+            source: "FOLDER_ID".to_string(),
+            remote_config: "gdrive".to_string(),
+            refreshed_at: refreshed_at.to_rfc3339(),
+            entries: vec![entry("album/a.mp3", 1), entry("album/b.mp3", 2)],
+        }
+    }
+
+    #[test]
+    fn changes_ignore_the_order_of_entries() {
+        let old = vec![entry("album/a.mp3", 1), entry("album/b.mp3", 2)];
+        let reordered = vec![entry("album/b.mp3", 2), entry("album/a.mp3", 1)];
+        let resized = vec![entry("album/a.mp3", 1), entry("album/b.mp3", 3)];
+
+        assert!(!has_changed(&old, &reordered));
+        assert!(has_changed(&old, &resized));
+        assert!(has_changed(&old, &old[..1]));
+    }
+
+    #[test]
+    fn only_older_listings_are_stale() {
+        let now = Local::now();
+
+        assert!(!is_stale(&manifest(now), now));
+        assert!(!is_stale(
+            &manifest(now - chrono::Duration::minutes(5)),
+            now
+        ));
+        assert!(is_stale(&manifest(now - chrono::Duration::hours(7)), now));
+
+        let mut broken = manifest(now);
+        broken.refreshed_at = "yesterday".to_string();
+        assert!(is_stale(&broken, now));
+    }
+
+    #[tokio::test]
+    async fn manifests_are_saved_and_read_back() {
+        let path = std::env::temp_dir()
+            .join(format!("manifest-{}", std::process::id()))
+            .join("gdrive-FOLDER_ID.json");
+        let saved = manifest(Local::now());
+
+        write_manifest(&path, &saved).await.unwrap();
+        // Writing again replaces the manifest without leaving temporary files
+        write_manifest(&path, &saved).await.unwrap();
+        let mut names = tokio::fs::read_dir(path.parent().unwrap()).await.unwrap();
+        let mut count = 0;
+        while names.next_entry().await.unwrap().is_some() {
+            count += 1;
+        }
+        let read = read_manifest(&path).await.unwrap();
+        tokio::fs::write(&path, b"{\"source\":").await.unwrap();
+        let corrupt = read_manifest(&path).await;
+        tokio::fs::remove_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(read.entries, saved.entries);
+        assert_eq!(read.refreshed_at, saved.refreshed_at);
+        assert!(corrupt.is_none());
+    }
+}
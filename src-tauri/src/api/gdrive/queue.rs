@@ -0,0 +1,690 @@
+use super::backups;
+use super::download::{
+    self, CancelledJobs, DownloadConfig, DownloadStatus, DownloadSummary, JobOutcome,
+};
+use super::history::{RunOrigin, RunRecorder};
+use super::scheduler::Scheduler;
+use super::tuning::TransferOptions;
+use super::utils::write_atomic;
+use crate::api::rclone::{self, ServerState, TransferredFile};
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
+use tokio::sync::Mutex;
+
+const DEFAULT_MAX_CONCURRENT: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueJobStatus {
+    Pending,
+    Running,
+    Paused,
+    Completed,
+    /// Finished, but some files could not be transferred
+    PartiallyFailed,
+    Failed,
+    Cancelled,
+}
+
+impl QueueJobStatus {
+    fn is_finished(self) -> bool {
+        matches!(
+            self,
+            Self::Completed | Self::PartiallyFailed | Self::Failed | Self::Cancelled
+        )
+    }
+
+    /// Whether the job got through the whole download
+    fn is_done(self) -> bool {
+        matches!(self, Self::Completed | Self::PartiallyFailed)
+    }
+}
+
+/// A download or sync request tracked by the transfer queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueJob {
+    pub id: u64,
+    pub config: DownloadConfig,
+    pub status: QueueJobStatus,
+    pub error: Option<String>,
+    pub rclone_job_id: Option<i64>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    /// What the last run of the job did, once it got through the download
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<DownloadSummary>,
+    /// Set when a sync profile queued the job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<RunOrigin>,
+}
+
+impl QueueJob {
+    fn origin(&self) -> RunOrigin {
+        self.origin
+            .clone()
+            .unwrap_or(RunOrigin::Queue { job_id: self.id })
+    }
+
+    /// The backup folder an unfinished job goes on writing to when it
+    /// runs again
+    pub(super) fn backup_in_use(&self) -> Option<&str> {
+        if self.status.is_finished() {
+            return None;
+        }
+        self.config.resume_point()?.backup_path.as_deref()
+    }
+
+    /// The sync profile that queued the job, if any
+    fn profile_id(&self) -> Option<u64> {
+        match self.origin {
+            Some(RunOrigin::Schedule { profile_id, .. }) => Some(profile_id),
+            _ => None,
+        }
+    }
+}
+
+/// How one attempt at a queued job ended
+struct JobRun {
+    outcome: Result<JobOutcome, AppError>,
+    summary: Result<DownloadSummary, AppError>,
+}
+
+impl JobRun {
+    fn failed(e: AppError) -> Self {
+        Self {
+            outcome: Err(e.clone()),
+            summary: Err(e),
+        }
+    }
+}
+
+/// Queue contents as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueueSnapshot {
+    next_id: u64,
+    max_concurrent: usize,
+    jobs: Vec<QueueJob>,
+}
+
+impl Default for QueueSnapshot {
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            jobs: Vec::new(),
+        }
+    }
+}
+
+pub struct JobQueue {
+    state: Mutex<QueueSnapshot>,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(QueueSnapshot::default()),
+        }
+    }
+}
+
+impl JobQueue {
+    fn get_queue_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+        Ok(app
+            .path()
+            .app_local_data_dir()
+            .map_err(|e| AppError::Internal(format!("Failed to get app data dir: {}", e)))?
+            .join("queue.json"))
+    }
+
+    /// Read the queue saved on disk. It is read before the queue is managed,
+    /// so no job can be enqueued before it and then be overwritten.
+    /// Jobs that were running when the app closed are queued again,
+    /// paused jobs stay paused until they are resumed.
+    pub fn restore(app: &AppHandle) -> Result<Self, AppError> {
+        let path = Self::get_queue_path(app)?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| AppError::io("Failed to read queue file", e))?;
+        let mut snapshot: QueueSnapshot = serde_json::from_str(&content)
+            .map_err(|e| AppError::Internal(format!("Failed to parse queue file: {}", e)))?;
+
+        for job in snapshot.jobs.iter_mut() {
+            if job.status == QueueJobStatus::Running {
+                job.status = QueueJobStatus::Pending;
+                job.rclone_job_id = None;
+            }
+        }
+
+        Ok(Self {
+            state: Mutex::new(snapshot),
+        })
+    }
+
+    /// Start the pending jobs of the managed queue
+    pub async fn start(app: &AppHandle) {
+        Self::watch_server(app);
+        Self::schedule(app).await;
+    }
+
+    /// Pick up jobs interrupted by a server crash once the server is back.
+    fn watch_server(app: &AppHandle) {
+        let handle = app.clone();
+        app.listen("rclone-server-status", move |event| {
+            let Ok(status) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+                return;
+            };
+            if status["state"] == "running" {
+                let app = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    Self::schedule(&app).await;
+                });
+            }
+        });
+    }
+
+    /// Write the queue to disk and notify the frontend.
+    async fn save(app: &AppHandle, snapshot: &QueueSnapshot) {
+        let _ = app.emit("transfer-queue-updated", &snapshot.jobs);
+
+        if let Err(e) = Self::write(app, snapshot).await {
+            crate::error::report_background_error(app, "Failed to save the transfer queue", e);
+        }
+    }
+
+    async fn write(app: &AppHandle, snapshot: &QueueSnapshot) -> Result<(), AppError> {
+        let path = Self::get_queue_path(app)?;
+        let content = serde_json::to_string_pretty(snapshot)?;
+        write_atomic(&path, content.as_bytes(), "queue file").await
+    }
+
+    /// Add a job to the end of the queue, start it if a slot is free
+    /// and return its queue id
+    pub(super) async fn enqueue(
+        app: &AppHandle,
+        config: DownloadConfig,
+        origin: Option<RunOrigin>,
+    ) -> u64 {
+        let id = {
+            let queue = app.state::<JobQueue>();
+            let mut state = queue.state.lock().await;
+            let id = state.next_id;
+            state.next_id += 1;
+            state.jobs.push(QueueJob {
+                id,
+                config,
+                status: QueueJobStatus::Pending,
+                error: None,
+                rclone_job_id: None,
+                created_at: chrono::Local::now().to_rfc3339(),
+                finished_at: None,
+                summary: None,
+                origin,
+            });
+            Self::save(app, &state).await;
+            id
+        };
+
+        Self::schedule(app).await;
+        id
+    }
+
+    /// Ids of the sync profiles with a queued job that has not finished yet
+    pub(super) async fn profiles_in_progress(app: &AppHandle) -> Vec<u64> {
+        let queue = app.state::<JobQueue>();
+        let state = queue.state.lock().await;
+        state
+            .jobs
+            .iter()
+            .filter(|job| !job.status.is_finished())
+            .filter_map(QueueJob::profile_id)
+            .collect()
+    }
+
+    /// Backup folders that jobs which haven't finished will still write to
+    pub(super) async fn backups_in_use(app: &AppHandle) -> Vec<String> {
+        let queue = app.state::<JobQueue>();
+        let state = queue.state.lock().await;
+        state
+            .jobs
+            .iter()
+            .filter_map(QueueJob::backup_in_use)
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Start pending jobs until the concurrency limit is reached.
+    async fn schedule(app: &AppHandle) {
+        while let Some(job) = Self::claim_next(app).await {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                Self::run_worker(&app, job).await;
+            });
+        }
+    }
+
+    /// Mark the next pending job as running if a slot is free.
+    async fn claim_next(app: &AppHandle) -> Option<QueueJob> {
+        let queue = app.state::<JobQueue>();
+        let mut state = queue.state.lock().await;
+
+        let running = state
+            .jobs
+            .iter()
+            .filter(|job| job.status == QueueJobStatus::Running)
+            .count();
+        if running >= state.max_concurrent {
+            return None;
+        }
+
+        let job = state
+            .jobs
+            .iter_mut()
+            .find(|job| job.status == QueueJobStatus::Pending)?;
+        job.status = QueueJobStatus::Running;
+        job.error = None;
+        let claimed = job.clone();
+
+        Self::save(app, &state).await;
+        Some(claimed)
+    }
+
+    /// Run jobs one after another until no pending job can be claimed.
+    async fn run_worker(app: &AppHandle, mut job: QueueJob) {
+        loop {
+            let run = Self::execute(app, &job).await;
+            let interrupted = matches!(run.outcome, Ok(JobOutcome::Interrupted));
+            let finished = Self::finish(app, job.id, &run).await;
+
+            if let Some((status, backup_path)) = &finished
+                && status.is_done()
+            {
+                backups::prune_after_sync(app, &job.config, backup_path.as_deref()).await;
+            }
+
+            // A paused or interrupted run of a profile goes on later
+            if let Some(profile_id) = job.profile_id()
+                && finished.is_some_and(|(status, _)| status.is_finished())
+            {
+                Scheduler::finish(app, profile_id, job.created_at.clone(), run.summary).await;
+            }
+
+            // Wait for the server to come back before starting anything else
+            if interrupted && rclone::server_state() != ServerState::Running {
+                break;
+            }
+
+            match Self::claim_next(app).await {
+                Some(next) => job = next,
+                None => break,
+            }
+        }
+    }
+
+    /// Record how a job ended, returning its new status and the backup
+    /// folder it used, or `None` if it was removed from the queue
+    async fn finish(
+        app: &AppHandle,
+        id: u64,
+        run: &JobRun,
+    ) -> Option<(QueueJobStatus, Option<String>)> {
+        let queue = app.state::<JobQueue>();
+        let mut state = queue.state.lock().await;
+        let mut finished = None;
+        if let Some(job) = state.jobs.iter_mut().find(|job| job.id == id) {
+            let backup_path = job
+                .config
+                .resume_point()
+                .and_then(|resume| resume.backup_path.clone());
+            if job.status == QueueJobStatus::Running {
+                // A job that failed only because some files did still has
+                // a summary, so the summary decides before the outcome
+                match (&run.outcome, &run.summary) {
+                    (_, Ok(summary)) => {
+                        job.status = match summary.status {
+                            DownloadStatus::Completed => QueueJobStatus::Completed,
+                            DownloadStatus::PartiallyFailed => QueueJobStatus::PartiallyFailed,
+                        };
+                        job.summary = Some(summary.clone());
+                    }
+                    (Ok(JobOutcome::Completed), Err(_)) => job.status = QueueJobStatus::Completed,
+                    (Ok(JobOutcome::Cancelled), _) => job.status = QueueJobStatus::Cancelled,
+                    (Ok(JobOutcome::Interrupted), _) => {
+                        job.status = QueueJobStatus::Pending;
+                        job.error = Some(AppError::Interrupted.to_string());
+                    }
+                    (Err(e), Err(_)) => {
+                        job.status = QueueJobStatus::Failed;
+                        job.error = Some(e.to_string());
+                    }
+                }
+                if job.status.is_finished() {
+                    job.finished_at = Some(chrono::Local::now().to_rfc3339());
+                    job.config.forget_progress();
+                }
+            }
+            job.rclone_job_id = None;
+            finished = Some((job.status, backup_path));
+        }
+        Self::save(app, &state).await;
+        finished
+    }
+
+    async fn execute(app: &AppHandle, job: &QueueJob) -> JobRun {
+        let (id, config) = (job.id, &job.config);
+        let mut recorder = RunRecorder::start(app, job.origin(), config).await;
+        let started = match config.build_filesystem_paths() {
+            Ok(paths) => {
+                recorder.set_backup_path(paths.backup_path.clone());
+                download::start_queued_download(app, config, &paths)
+                    .await
+                    .map(|started| (started, paths.backup_path))
+            }
+            Err(e) => Err(e),
+        };
+        let ((client, jobid, monitor), backup_path) = match started {
+            Ok(started) => started,
+            Err(e) => {
+                recorder.finish(app, &Err(e.clone()), &[]).await;
+                return JobRun::failed(e);
+            }
+        };
+
+        let stopped = app.state::<CancelledJobs>();
+        stopped.track(jobid).await;
+
+        let queue = app.state::<JobQueue>();
+        let cancelled = {
+            let mut state = queue.state.lock().await;
+            let cancelled = match state.jobs.iter_mut().find(|job| job.id == id) {
+                Some(job) if job.status == QueueJobStatus::Running => {
+                    job.rclone_job_id = Some(jobid);
+                    job.config.keep_backup_path(backup_path);
+                    false
+                }
+                _ => true,
+            };
+            Self::save(app, &state).await;
+            cancelled
+        };
+
+        // The job was cancelled while it was being submitted
+        if cancelled && let Err(e) = download::stop_job(&client, &stopped, jobid).await {
+            recorder.finish(app, &Err(e.clone()), &[]).await;
+            return JobRun::failed(e);
+        }
+
+        let outcome = download::poll_job_completion(&client, &stopped, jobid).await;
+        let transferred = monitor.transferred().await;
+        Self::keep_progress(app, id, &transferred).await;
+        let result = download::job_result(&client, jobid, &outcome).await;
+        let run = recorder.finish(app, &result, &transferred).await;
+        JobRun {
+            outcome,
+            summary: result.map(|stats| DownloadSummary::new(stats, run.failed)),
+        }
+    }
+
+    /// Remember the files a job copied, so the next attempt after a pause
+    /// or an interruption leaves them out
+    async fn keep_progress(app: &AppHandle, id: u64, transferred: &[TransferredFile]) {
+        let queue = app.state::<JobQueue>();
+        let mut state = queue.state.lock().await;
+        if let Some(job) = state.jobs.iter_mut().find(|job| job.id == id) {
+            job.config.skip_transferred(
+                transferred
+                    .iter()
+                    .filter(|file| file.is_copied())
+                    .map(|file| file.name.clone()),
+            );
+        }
+    }
+}
+
+/// Add a download to the transfer queue and return its queue id
+#[tauri::command]
+pub async fn enqueue_download(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    remote_config: Option<String>,
+    sync_mode: bool,
+    create_subfolder: bool,
+    selected_files: Option<Vec<String>>,
+    create_backup: bool,
+    delete_excluded: bool,
+    track_renames: bool,
+    transfer_options: Option<TransferOptions>,
+) -> Result<u64, AppError> {
+    let config = DownloadConfig::new(
+        source,
+        destination,
+        remote_config,
+        sync_mode,
+        create_subfolder,
+        selected_files,
+        create_backup,
+        delete_excluded,
+        track_renames,
+    )?
+    .with_transfer_options(transfer_options.unwrap_or_default())?;
+
+    Ok(JobQueue::enqueue(&app, config, None).await)
+}
+
+#[tauri::command]
+pub async fn list_queue_jobs(state: State<'_, JobQueue>) -> Result<Vec<QueueJob>, AppError> {
+    Ok(state.state.lock().await.jobs.clone())
+}
+
+/// Move a job to a new position in the queue
+#[tauri::command]
+pub async fn move_queue_job(
+    app: AppHandle,
+    state: State<'_, JobQueue>,
+    id: u64,
+    position: usize,
+) -> Result<(), AppError> {
+    let mut queue = state.state.lock().await;
+    let index = queue
+        .jobs
+        .iter()
+        .position(|job| job.id == id)
+        .ok_or(AppError::NotFound(format!("Job {}", id)))?;
+
+    let job = queue.jobs.remove(index);
+    let position = position.min(queue.jobs.len());
+    queue.jobs.insert(position, job);
+
+    JobQueue::save(&app, &queue).await;
+    Ok(())
+}
+
+/// Cancel a pending or running job. Running jobs are stopped in rclone.
+#[tauri::command]
+pub async fn cancel_queue_job(
+    app: AppHandle,
+    state: State<'_, JobQueue>,
+    id: u64,
+) -> Result<(), AppError> {
+    let (rclone_job_id, idle_profile) = {
+        let mut queue = state.state.lock().await;
+        let job = queue
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or(AppError::NotFound(format!("Job {}", id)))?;
+
+        if job.status.is_finished() {
+            return Err(AppError::InvalidRequest(format!(
+                "Job {} has already finished",
+                id
+            )));
+        }
+
+        // A running job reports to its profile when its worker stops
+        let idle_profile = match job.status {
+            QueueJobStatus::Running => None,
+            _ => job
+                .profile_id()
+                .map(|profile_id| (profile_id, job.created_at.clone())),
+        };
+        job.status = QueueJobStatus::Cancelled;
+        job.finished_at = Some(chrono::Local::now().to_rfc3339());
+        let rclone_job_id = job.rclone_job_id;
+        JobQueue::save(&app, &queue).await;
+        (rclone_job_id, idle_profile)
+    };
+
+    if let Some((profile_id, started_at)) = idle_profile {
+        let cancelled = AppError::Cancelled("Download cancelled".to_string());
+        Scheduler::finish(&app, profile_id, started_at, Err(cancelled)).await;
+    }
+
+    if let Some(jobid) = rclone_job_id {
+        let client = crate::api::rclone::get_sdk_client(&app).await?;
+        download::stop_job(&client, &app.state::<CancelledJobs>(), jobid).await?;
+    }
+
+    Ok(())
+}
+
+/// Pause a pending or running job. A running job is stopped in rclone and
+/// remembers the files it copied so far, which resuming it leaves out
+/// instead of checking them again. The rest of the files are checked as
+/// usual, and replaced files still go to the backup folder of the first
+/// attempt. A download deleting excluded files checks every file again.
+#[tauri::command]
+pub async fn pause_queue_job(
+    app: AppHandle,
+    state: State<'_, JobQueue>,
+    id: u64,
+) -> Result<(), AppError> {
+    let rclone_job_id = {
+        let mut queue = state.state.lock().await;
+        let job = queue
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or(AppError::NotFound(format!("Job {}", id)))?;
+
+        if !matches!(
+            job.status,
+            QueueJobStatus::Pending | QueueJobStatus::Running
+        ) {
+            return Err(AppError::InvalidRequest(format!(
+                "Job {} cannot be paused",
+                id
+            )));
+        }
+
+        job.status = QueueJobStatus::Paused;
+        let rclone_job_id = job.rclone_job_id;
+        JobQueue::save(&app, &queue).await;
+        rclone_job_id
+    };
+
+    if let Some(jobid) = rclone_job_id {
+        let client = crate::api::rclone::get_sdk_client(&app).await?;
+        download::stop_job(&client, &app.state::<CancelledJobs>(), jobid).await?;
+    }
+
+    Ok(())
+}
+
+/// Put a paused job back into the queue
+#[tauri::command]
+pub async fn resume_queue_job(
+    app: AppHandle,
+    state: State<'_, JobQueue>,
+    id: u64,
+) -> Result<(), AppError> {
+    {
+        let mut queue = state.state.lock().await;
+        let job = queue
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or(AppError::NotFound(format!("Job {}", id)))?;
+
+        if job.status != QueueJobStatus::Paused {
+            return Err(AppError::InvalidRequest(format!(
+                "Job {} is not paused",
+                id
+            )));
+        }
+
+        job.status = QueueJobStatus::Pending;
+        JobQueue::save(&app, &queue).await;
+    }
+
+    JobQueue::schedule(&app).await;
+    Ok(())
+}
+
+/// Queue a partially failed, failed or cancelled job again
+#[tauri::command]
+pub async fn retry_queue_job(
+    app: AppHandle,
+    state: State<'_, JobQueue>,
+    id: u64,
+) -> Result<(), AppError> {
+    {
+        let mut queue = state.state.lock().await;
+        let job = queue
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or(AppError::NotFound(format!("Job {}", id)))?;
+
+        if !matches!(
+            job.status,
+            QueueJobStatus::PartiallyFailed | QueueJobStatus::Failed | QueueJobStatus::Cancelled
+        ) {
+            return Err(AppError::InvalidRequest(format!(
+                "Job {} cannot be retried",
+                id
+            )));
+        }
+
+        job.status = QueueJobStatus::Pending;
+        job.error = None;
+        job.finished_at = None;
+        job.summary = None;
+        job.config.forget_progress();
+        JobQueue::save(&app, &queue).await;
+    }
+
+    JobQueue::schedule(&app).await;
+    Ok(())
+}
+
+/// Set how many queued jobs may run at the same time
+#[tauri::command]
+pub async fn set_queue_concurrency(
+    app: AppHandle,
+    state: State<'_, JobQueue>,
+    max_concurrent: usize,
+) -> Result<(), AppError> {
+    if max_concurrent == 0 {
+        return Err(AppError::InvalidRequest(
+            "Concurrency must be at least 1".to_string(),
+        ));
+    }
+
+    {
+        let mut queue = state.state.lock().await;
+        queue.max_concurrent = max_concurrent;
+        JobQueue::save(&app, &queue).await;
+    }
+
+    JobQueue::schedule(&app).await;
+    Ok(())
+}
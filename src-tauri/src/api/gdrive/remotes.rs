@@ -1,4 +1,5 @@
 use crate::api::rclone;
+use crate::error::AppError;
 use crate::utils::extract_json;
 use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_shell::process::CommandEvent;
@@ -19,16 +20,17 @@ impl Default for GdriveAuthState {
 }
 
 #[tauri::command]
-pub async fn get_gdrive_remotes(app: AppHandle) -> Result<Vec<String>, String> {
+pub async fn get_gdrive_remotes(app: AppHandle) -> Result<Vec<String>, AppError> {
     let client = rclone::get_sdk_client(&app).await?;
+    list_drive_remotes(&client).await
+}
 
+/// Names of the configured Google Drive remotes, sorted
+async fn list_drive_remotes(client: &rclone_sdk::Client) -> Result<Vec<String>, AppError> {
     // config/dump
-    let response = client
-        .config_dump(None, None)
-        .await
-        .map_err(|e| format!("Failed to fetch remotes: {}", e))?;
+    let response = client.config_dump(None, None).await?;
 
-    let val = serde_json::to_value(response.into_inner()).map_err(|e| e.to_string())?;
+    let val = serde_json::to_value(response.into_inner())?;
 
     let mut remotes = Vec::new();
     if let Some(obj) = val.as_object() {
@@ -49,14 +51,16 @@ pub async fn get_gdrive_remotes(app: AppHandle) -> Result<Vec<String>, String> {
 pub async fn create_gdrive_remote(
     app: AppHandle,
     state: State<'_, GdriveAuthState>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     // Authorize with CLI (interactive)
     let sidecar_command = rclone::get_rclone_command(&app)?;
 
     let (mut command_rx, child) = sidecar_command
         .args(&["authorize", "drive", "--auth-no-open-browser"])
         .spawn()
-        .map_err(|e| format!("Failed to spawn rclone sidecar: {}", e))?;
+        .map_err(|e| {
+            AppError::RcloneUnavailable(format!("Failed to spawn rclone sidecar: {}", e))
+        })?;
 
     let manager = app.state::<crate::SidecarManager>();
     let child = manager.add(child);
@@ -78,7 +82,7 @@ pub async fn create_gdrive_remote(
         tokio::select! {
             _ = &mut rx => {
                 let _ = child.kill();
-                break Err("Cancelled by user".to_string());
+                break Err(AppError::Cancelled("Cancelled by user".to_string()));
             }
             maybe_event = command_rx.recv() => {
                 match maybe_event {
@@ -94,11 +98,11 @@ pub async fn create_gdrive_remote(
                         }
                     }
                     Some(CommandEvent::Error(err)) => {
-                        break Err(format!("Process error: {}", err));
+                        break Err(AppError::Rclone(format!("Process error: {}", err)));
                     }
                     Some(CommandEvent::Terminated(term)) => {
                         if term.code.unwrap_or(0) != 0 {
-                             break Err(format!("Rclone authorize failed with code {:?}", term.code));
+                             break Err(AppError::Rclone(format!("Rclone authorize failed with code {:?}", term.code)));
                         }
                         break Ok(auth_output.clone());
                     }
@@ -117,24 +121,12 @@ pub async fn create_gdrive_remote(
 
     match result {
         Ok(output) => {
-            let token = extract_json(&output).ok_or("Failed to extract token from auth output")?;
-
-            let params = serde_json::json!({
-                "token": token
-            });
+            let token = extract_json(&output).ok_or(AppError::Rclone(
+                "Failed to extract token from auth output".to_string(),
+            ))?;
 
             let client = rclone::get_sdk_client(&app).await?;
-            client
-                .config_create(
-                    Some(true),
-                    None,
-                    DEFAULT_RCLONE_CONFIG_NAME,
-                    None,
-                    &params.to_string(),
-                    "drive",
-                )
-                .await
-                .map_err(|e| format!("Failed to create config context: {}", e))?;
+            create_drive_remote(&client, &token).await?;
 
             Ok(DEFAULT_RCLONE_CONFIG_NAME.to_string())
         }
@@ -142,11 +134,70 @@ pub async fn create_gdrive_remote(
     }
 }
 
+/// Save the app's Drive remote with a token from `rclone authorize`
+async fn create_drive_remote(client: &rclone_sdk::Client, token: &str) -> Result<(), AppError> {
+    let params = serde_json::json!({
+        "token": token
+    });
+
+    client
+        .config_create(
+            Some(true),
+            None,
+            DEFAULT_RCLONE_CONFIG_NAME,
+            None,
+            &params.to_string(),
+            "drive",
+        )
+        .await?;
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn cancel_gdrive_auth(state: State<'_, GdriveAuthState>) -> Result<(), String> {
+pub async fn cancel_gdrive_auth(state: State<'_, GdriveAuthState>) -> Result<(), AppError> {
     let mut lock = state.auth_cancel_tx.lock().await;
     if let Some(tx) = lock.take() {
         let _ = tx.send(());
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rclone::mock::MockRcServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn only_drive_remotes_are_listed() {
+        let server = MockRcServer::start().await;
+        server.state().remotes = json!({
+            "zeta": {"type": "drive"},
+            "backup": {"type": "s3"},
+            "alpha": {"type": "drive"},
+        })
+        .as_object()
+        .unwrap()
+        .clone();
+
+        let remotes = list_drive_remotes(&server.client()).await.unwrap();
+
+        assert_eq!(remotes, vec!["alpha", "zeta"]);
+    }
+
+    #[tokio::test]
+    async fn created_remote_stores_the_token() {
+        let server = MockRcServer::start().await;
+        let token = r#"{"access_token":"abc","expiry":"2030-01-01T00:00:00Z"}"#;
+
+        create_drive_remote(&server.client(), token).await.unwrap();
+
+        let remote = server.state().remotes[DEFAULT_RCLONE_CONFIG_NAME].clone();
+        assert_eq!(remote["type"], "drive");
+        assert_eq!(remote["token"], token);
+        assert_eq!(
+            list_drive_remotes(&server.client()).await.unwrap(),
+            vec![DEFAULT_RCLONE_CONFIG_NAME]
+        );
+    }
+}
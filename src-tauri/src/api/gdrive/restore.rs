@@ -0,0 +1,510 @@
+use super::backups;
+use super::download;
+use super::history::FailedFile;
+use crate::api::rclone;
+use crate::error::AppError;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::AppHandle;
+
+/// How a backed up file compares to the file at the same path in the archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupFileStatus {
+    /// The file is no longer in the archive
+    Missing,
+    /// The archive has the same file
+    Identical,
+    /// The archive has a different file that isn't newer than the backup
+    Changed,
+    /// The archive has a newer version of the file
+    NewerInArchive,
+}
+
+/// A file in a backup, compared to the archive
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<String>,
+    pub status: BackupFileStatus,
+    pub current_size: Option<u64>,
+    pub current_modified: Option<String>,
+}
+
+/// What to do when the archive has a different version of a file being
+/// restored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreConflict {
+    /// Keep the archive's version
+    #[default]
+    Skip,
+    /// Replace the archive's version with the backup
+    Overwrite,
+    /// Restore the backup next to the archive's version under a new name
+    KeepBoth,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    /// Archive paths that were written
+    pub restored: Vec<String>,
+    /// Backup paths that were left alone
+    pub skipped: Vec<String>,
+    /// Archive paths that could not be written, with the reason
+    pub failed: Vec<FailedFile>,
+}
+
+/// A single file copy from the backup into the archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RestoreCopy {
+    src: String,
+    dst: String,
+}
+
+/// Size and modification time of a local file
+#[derive(Debug, Clone, Copy)]
+struct FileInfo {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+fn format_time(time: Option<SystemTime>) -> Option<String> {
+    time.map(|time| DateTime::<Local>::from(time).to_rfc3339())
+}
+
+/// Every file below a folder, keyed by its path relative to the folder
+/// with `/` separators
+fn walk_files(root: &Path) -> Result<HashMap<String, FileInfo>, AppError> {
+    let mut files = HashMap::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let entries =
+            std::fs::read_dir(&dir).map_err(|e| AppError::io("Failed to read directory", e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| AppError::io("Failed to read directory", e))?;
+            let path = entry.path();
+            let metadata = path
+                .symlink_metadata()
+                .map_err(|e| AppError::io("Failed to read file", e))?;
+            if metadata.is_dir() {
+                stack.push(path);
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.insert(
+                    relative.to_string_lossy().replace('\\', "/"),
+                    FileInfo {
+                        size: metadata.len(),
+                        modified: metadata.modified().ok(),
+                    },
+                );
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn compare(backup: &FileInfo, current: Option<&FileInfo>) -> BackupFileStatus {
+    let Some(current) = current else {
+        return BackupFileStatus::Missing;
+    };
+
+    if current.size == backup.size && current.modified == backup.modified {
+        BackupFileStatus::Identical
+    } else if current.modified > backup.modified {
+        BackupFileStatus::NewerInArchive
+    } else {
+        BackupFileStatus::Changed
+    }
+}
+
+/// Compare every file in a backup folder with the archive folder
+fn diff_dirs(backup_dir: &Path, dst_dir: &Path) -> Result<Vec<BackupFile>, AppError> {
+    let backup_files = walk_files(backup_dir)?;
+    let current_files = if dst_dir.is_dir() {
+        walk_files(dst_dir)?
+    } else {
+        HashMap::new()
+    };
+
+    let mut files: Vec<BackupFile> = backup_files
+        .into_iter()
+        .map(|(path, info)| {
+            let current = current_files.get(&path);
+            BackupFile {
+                status: compare(&info, current),
+                size: info.size,
+                modified: format_time(info.modified),
+                current_size: current.map(|c| c.size),
+                current_modified: current.and_then(|c| format_time(c.modified)),
+                path,
+            }
+        })
+        .collect();
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// The archive path a file is restored to when both versions are kept,
+/// e.g. `album/song (Backup-KAR-20260102_030405).mp3`
+fn keep_both_path(path: &str, backup_name: &str) -> String {
+    let (dir, file) = match path.rsplit_once('/') {
+        Some((dir, file)) => (format!("{}/", dir), file),
+        None => (String::new(), path),
+    };
+    let (stem, extension) = match file.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (file, String::new()),
+    };
+    format!("{}{} ({}){}", dir, stem, backup_name, extension)
+}
+
+/// Decide which files to copy for a restore. `selected` limits the restore
+/// to some backup paths, otherwise the whole backup is restored.
+fn plan_restore(
+    files: &[BackupFile],
+    selected: Option<&[String]>,
+    conflict: RestoreConflict,
+    backup_name: &str,
+) -> Result<(Vec<RestoreCopy>, Vec<String>), AppError> {
+    let chosen: Vec<&BackupFile> = match selected {
+        Some(selected) => selected
+            .iter()
+            .map(|path| {
+                let path = path.trim_start_matches('/');
+                files
+                    .iter()
+                    .find(|file| file.path == path)
+                    .ok_or(AppError::NotFound(format!("{} in {}", path, backup_name)))
+            })
+            .collect::<Result<_, _>>()?,
+        None => files.iter().collect(),
+    };
+
+    let mut copies = Vec::new();
+    let mut skipped = Vec::new();
+    for file in chosen {
+        let dst = match (file.status, conflict) {
+            (BackupFileStatus::Identical, _)
+            | (
+                BackupFileStatus::NewerInArchive | BackupFileStatus::Changed,
+                RestoreConflict::Skip,
+            ) => {
+                skipped.push(file.path.clone());
+                continue;
+            }
+            (
+                BackupFileStatus::NewerInArchive | BackupFileStatus::Changed,
+                RestoreConflict::KeepBoth,
+            ) => keep_both_path(&file.path, backup_name),
+            _ => file.path.clone(),
+        };
+        copies.push(RestoreCopy {
+            src: file.path.clone(),
+            dst,
+        });
+    }
+
+    Ok((copies, skipped))
+}
+
+/// Copy the planned files from the backup into the archive through rclone,
+/// returning the archive paths written and the ones that failed. A failed
+/// file doesn't stop the others from being restored.
+async fn copy_files(
+    client: &rclone_sdk::Client,
+    backup_dir: &Path,
+    dst_dir: &Path,
+    copies: &[RestoreCopy],
+) -> (Vec<String>, Vec<FailedFile>) {
+    let src_fs = download::local_fs(backup_dir);
+    let dst_fs = download::local_fs(dst_dir);
+
+    let mut restored = Vec::new();
+    let mut failed = Vec::new();
+    for copy in copies {
+        match client
+            .operations_copyfile(None, None, &dst_fs, &copy.dst, &src_fs, &copy.src)
+            .await
+        {
+            Ok(_) => restored.push(copy.dst.clone()),
+            Err(e) => failed.push(FailedFile {
+                path: Some(copy.dst.clone()),
+                reason: AppError::from(e).to_string(),
+            }),
+        }
+    }
+    (restored, failed)
+}
+
+fn resolve_dirs(
+    destination: &str,
+    create_subfolder: bool,
+    backup: &str,
+) -> Result<(PathBuf, PathBuf), AppError> {
+    let backup_dir = backups::get_backup_path(destination, create_subfolder, backup)?;
+    let dst_dir = download::destination_path(destination, create_subfolder);
+    Ok((backup_dir, dst_dir))
+}
+
+/// List the files in a backup and how they compare to the archive
+#[tauri::command]
+pub async fn diff_backup(
+    destination: String,
+    create_subfolder: bool,
+    backup: String,
+) -> Result<Vec<BackupFile>, AppError> {
+    let (backup_dir, dst_dir) = resolve_dirs(&destination, create_subfolder, &backup)?;
+    tauri::async_runtime::spawn_blocking(move || diff_dirs(&backup_dir, &dst_dir))
+        .await
+        .map_err(|e| AppError::Internal(format!("Comparing backup failed: {}", e)))?
+}
+
+/// Restore files from a backup into the archive, or the whole backup when
+/// no files are given. Files the archive already has are skipped, and files
+/// it has a different version of follow `conflict`.
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    destination: String,
+    create_subfolder: bool,
+    backup: String,
+    files: Option<Vec<String>>,
+    conflict: Option<RestoreConflict>,
+) -> Result<RestoreResult, AppError> {
+    let (backup_dir, dst_dir) = resolve_dirs(&destination, create_subfolder, &backup)?;
+
+    let diff = {
+        let (backup_dir, dst_dir) = (backup_dir.clone(), dst_dir.clone());
+        tauri::async_runtime::spawn_blocking(move || diff_dirs(&backup_dir, &dst_dir))
+            .await
+            .map_err(|e| AppError::Internal(format!("Comparing backup failed: {}", e)))??
+    };
+    let (copies, skipped) = plan_restore(
+        &diff,
+        files.as_deref(),
+        conflict.unwrap_or_default(),
+        &backup,
+    )?;
+
+    let client = rclone::get_sdk_client(&app).await?;
+    let (restored, failed) = copy_files(&client, &backup_dir, &dst_dir, &copies).await;
+
+    Ok(RestoreResult {
+        restored,
+        skipped,
+        failed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rclone::mock::MockRcServer;
+    use std::time::Duration;
+
+    const BACKUP: &str = "Backup-KAR-20260102_030405";
+
+    fn file(path: &str, status: BackupFileStatus) -> BackupFile {
+        BackupFile {
+            path: path.to_string(),
+            size: 1,
+            modified: None,
+            status,
+            current_size: None,
+            current_modified: None,
+        }
+    }
+
+    #[test]
+    fn backup_is_compared_with_the_archive() {
+        let root = std::env::temp_dir().join(format!("restore-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let backup_dir = root.join(BACKUP);
+        let dst_dir = root.join("archive");
+        std::fs::create_dir_all(backup_dir.join("album")).unwrap();
+        std::fs::create_dir_all(dst_dir.join("album")).unwrap();
+
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        for name in ["gone.mp3", "same.mp3", "updated.mp3"] {
+            let path = backup_dir.join("album").join(name);
+            std::fs::write(&path, [0; 4]).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+        std::fs::copy(
+            backup_dir.join("album/same.mp3"),
+            dst_dir.join("album/same.mp3"),
+        )
+        .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(dst_dir.join("album/same.mp3"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        std::fs::write(dst_dir.join("album/updated.mp3"), [0; 8]).unwrap();
+
+        let diff = diff_dirs(&backup_dir, &dst_dir).unwrap();
+        let statuses: Vec<(&str, BackupFileStatus)> = diff
+            .iter()
+            .map(|file| (file.path.as_str(), file.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("album/gone.mp3", BackupFileStatus::Missing),
+                ("album/same.mp3", BackupFileStatus::Identical),
+                ("album/updated.mp3", BackupFileStatus::NewerInArchive),
+            ]
+        );
+        assert_eq!(diff[2].current_size, Some(8));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn conflicts_follow_the_chosen_handling() {
+        let files = vec![
+            file("gone.mp3", BackupFileStatus::Missing),
+            file("same.mp3", BackupFileStatus::Identical),
+            file("album/newer.mp3", BackupFileStatus::NewerInArchive),
+            file("older.mp3", BackupFileStatus::Changed),
+        ];
+
+        let (copies, skipped) = plan_restore(&files, None, RestoreConflict::Skip, BACKUP).unwrap();
+        let dsts: Vec<&str> = copies.iter().map(|copy| copy.dst.as_str()).collect();
+        assert_eq!(dsts, ["gone.mp3"]);
+        assert_eq!(skipped, ["same.mp3", "album/newer.mp3", "older.mp3"]);
+
+        let (copies, _) = plan_restore(&files, None, RestoreConflict::Overwrite, BACKUP).unwrap();
+        assert!(copies.contains(&RestoreCopy {
+            src: "album/newer.mp3".to_string(),
+            dst: "album/newer.mp3".to_string(),
+        }));
+
+        let selected = vec!["/album/newer.mp3".to_string()];
+        let (copies, skipped) =
+            plan_restore(&files, Some(&selected), RestoreConflict::KeepBoth, BACKUP).unwrap();
+        assert_eq!(
+            copies,
+            [RestoreCopy {
+                src: "album/newer.mp3".to_string(),
+                dst: "album/newer (Backup-KAR-20260102_030405).mp3".to_string(),
+            }]
+        );
+        assert!(skipped.is_empty());
+
+        let missing = vec!["nope.mp3".to_string()];
+        let result = plan_restore(&files, Some(&missing), RestoreConflict::Skip, BACKUP);
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn changed_files_are_not_overwritten_by_default() {
+        let files = vec![file("album/edited.mp3", BackupFileStatus::Changed)];
+
+        let (copies, skipped) =
+            plan_restore(&files, None, RestoreConflict::default(), BACKUP).unwrap();
+        assert!(copies.is_empty());
+        assert_eq!(skipped, ["album/edited.mp3"]);
+
+        let (copies, _) = plan_restore(&files, None, RestoreConflict::KeepBoth, BACKUP).unwrap();
+        assert_eq!(
+            copies,
+            [RestoreCopy {
+                src: "album/edited.mp3".to_string(),
+                dst: "album/edited (Backup-KAR-20260102_030405).mp3".to_string(),
+            }]
+        );
+
+        let (copies, _) = plan_restore(&files, None, RestoreConflict::Overwrite, BACKUP).unwrap();
+        assert_eq!(copies[0].dst, "album/edited.mp3");
+    }
+
+    #[test]
+    fn keep_both_names_keep_the_extension() {
+        assert_eq!(
+            keep_both_path("song.mp3", BACKUP),
+            format!("song ({}).mp3", BACKUP)
+        );
+        assert_eq!(
+            keep_both_path("a/.hidden", BACKUP),
+            format!("a/.hidden ({})", BACKUP)
+        );
+        assert_eq!(
+            keep_both_path("a.b/README", BACKUP),
+            format!("a.b/README ({})", BACKUP)
+        );
+    }
+
+    #[tokio::test]
+    async fn files_are_copied_with_copyfile() {
+        let server = MockRcServer::start().await;
+        let copies = vec![RestoreCopy {
+            src: "album/song.mp3".to_string(),
+            dst: "album/song (Backup-KAR-20260102_030405).mp3".to_string(),
+        }];
+
+        let (restored, failed) = copy_files(
+            &server.client(),
+            Path::new("/music/Backup-KAR-20260102_030405"),
+            Path::new("/music/archive"),
+            &copies,
+        )
+        .await;
+        assert_eq!(restored, ["album/song (Backup-KAR-20260102_030405).mp3"]);
+        assert!(failed.is_empty());
+
+        let calls = server.state().calls.clone();
+        let (path, params) = calls.last().unwrap();
+        assert_eq!(path, "operations/copyfile");
+        assert_eq!(params["srcFs"], "/music/Backup-KAR-20260102_030405");
+        assert_eq!(params["srcRemote"], "album/song.mp3");
+        assert_eq!(params["dstFs"], "/music/archive");
+        assert_eq!(
+            params["dstRemote"],
+            "album/song (Backup-KAR-20260102_030405).mp3"
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_copies_do_not_stop_the_restore() {
+        let server = MockRcServer::start().await;
+        server.state().copy_errors.insert(
+            "album/locked.mp3".to_string(),
+            "permission denied".to_string(),
+        );
+        let copies: Vec<RestoreCopy> = ["album/a.mp3", "album/locked.mp3", "album/b.mp3"]
+            .into_iter()
+            .map(|path| RestoreCopy {
+                src: path.to_string(),
+                dst: path.to_string(),
+            })
+            .collect();
+
+        let (restored, failed) = copy_files(
+            &server.client(),
+            Path::new("/music/Backup-KAR-20260102_030405"),
+            Path::new("/music/archive"),
+            &copies,
+        )
+        .await;
+        assert_eq!(restored, ["album/a.mp3", "album/b.mp3"]);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].path.as_deref(), Some("album/locked.mp3"));
+        assert!(failed[0].reason.contains("permission denied"));
+    }
+}
@@ -0,0 +1,526 @@
+use super::download::{DownloadConfig, DownloadStatus, DownloadSummary};
+use super::history::RunOrigin;
+use super::queue::JobQueue;
+use super::utils::write_atomic;
+use crate::error::AppError;
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Mutex;
+
+/// How often due profiles are looked for
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Number of past runs kept for each profile
+const MAX_RECORDED_RUNS: usize = 20;
+
+/// When a sync profile runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Schedule {
+    /// Every given number of minutes
+    Interval { minutes: u32 },
+    /// At a local time of day, on the given weekdays (0 is Monday) or every
+    /// day when none are given
+    Daily {
+        hour: u32,
+        minute: u32,
+        #[serde(default)]
+        weekdays: Vec<u32>,
+    },
+}
+
+impl Schedule {
+    fn validate(&self) -> Result<(), AppError> {
+        let valid = match self {
+            Self::Interval { minutes } => *minutes > 0,
+            Self::Daily {
+                hour,
+                minute,
+                weekdays,
+            } => *hour < 24 && *minute < 60 && weekdays.iter().all(|day| *day < 7),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(AppError::InvalidRequest(format!(
+                "Invalid schedule: {:?}",
+                self
+            )))
+        }
+    }
+
+    /// The first time after the given one the schedule is due
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        match self {
+            Self::Interval { minutes } => Some(after.clone() + Duration::minutes(*minutes as i64)),
+            Self::Daily {
+                hour,
+                minute,
+                weekdays,
+            } => (0..=7).find_map(|offset| {
+                let date = after.date_naive() + Duration::days(offset);
+                if !weekdays.is_empty()
+                    && !weekdays.contains(&date.weekday().num_days_from_monday())
+                {
+                    return None;
+                }
+                // Times skipped by a DST change don't exist, try the next day
+                let time = after
+                    .timezone()
+                    .from_local_datetime(&date.and_hms_opt(*hour, *minute, 0)?)
+                    .earliest()?;
+                (time > *after).then_some(time)
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProfileRunStatus {
+    Completed,
+    PartiallyFailed,
+    Failed,
+    Cancelled,
+}
+
+/// The outcome of one scheduled or manual run of a profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileRun {
+    pub started_at: String,
+    pub finished_at: String,
+    pub status: ProfileRunStatus,
+    pub error: Option<String>,
+}
+
+/// A download that runs on a schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProfile {
+    pub id: u64,
+    pub name: String,
+    pub config: DownloadConfig,
+    pub schedule: Schedule,
+    pub enabled: bool,
+    pub next_run: Option<String>,
+    #[serde(default)]
+    pub running: bool,
+    /// Past runs, oldest first
+    #[serde(default)]
+    pub runs: Vec<ProfileRun>,
+}
+
+impl SyncProfile {
+    fn plan_next_run(&mut self, after: &DateTime<Local>) {
+        self.next_run = if self.enabled {
+            self.schedule
+                .next_after(after)
+                .map(|time| time.to_rfc3339())
+        } else {
+            None
+        };
+    }
+
+    fn is_due(&self, now: &DateTime<Local>) -> bool {
+        self.enabled
+            && !self.running
+            && self
+                .next_run
+                .as_deref()
+                .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                .is_some_and(|time| time <= *now)
+    }
+}
+
+/// Profiles as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchedulerSnapshot {
+    next_id: u64,
+    profiles: Vec<SyncProfile>,
+}
+
+impl Default for SchedulerSnapshot {
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            profiles: Vec::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    state: Mutex<SchedulerSnapshot>,
+    /// Whether any profile is enabled, so the app keeps running in the tray
+    /// when its window is closed
+    has_enabled: AtomicBool,
+}
+
+impl Scheduler {
+    fn get_profiles_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+        Ok(app
+            .path()
+            .app_local_data_dir()
+            .map_err(|e| AppError::Internal(format!("Failed to get app data dir: {}", e)))?
+            .join("schedules.json"))
+    }
+
+    /// Restore the profiles from disk and start checking for due runs.
+    /// Runs missed while the app was closed start right away.
+    pub async fn load(app: &AppHandle) -> Result<(), AppError> {
+        let path = Self::get_profiles_path(app)?;
+        if path.exists() {
+            let content = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| AppError::io("Failed to read schedules file", e))?;
+            let mut snapshot: SchedulerSnapshot = serde_json::from_str(&content).map_err(|e| {
+                AppError::Internal(format!("Failed to parse schedules file: {}", e))
+            })?;
+
+            // Runs still in the queue carry on where the queue left them
+            let queued = JobQueue::profiles_in_progress(app).await;
+            for profile in snapshot.profiles.iter_mut() {
+                profile.running = queued.contains(&profile.id);
+            }
+
+            let scheduler = app.state::<Scheduler>();
+            scheduler.update_enabled(&snapshot);
+            *scheduler.state.lock().await = snapshot;
+        }
+
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                Self::run_due(&app).await;
+                tokio::time::sleep(TICK_INTERVAL).await;
+            }
+        });
+        Ok(())
+    }
+
+    /// Whether closing the window should keep the app running in the tray
+    pub fn keeps_running(&self) -> bool {
+        self.has_enabled.load(Ordering::Relaxed)
+    }
+
+    fn update_enabled(&self, snapshot: &SchedulerSnapshot) {
+        let enabled = snapshot.profiles.iter().any(|profile| profile.enabled);
+        self.has_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Write the profiles to disk and notify the frontend.
+    async fn save(app: &AppHandle, snapshot: &SchedulerSnapshot) {
+        app.state::<Scheduler>().update_enabled(snapshot);
+        let _ = app.emit("sync-profiles-updated", &snapshot.profiles);
+
+        if let Err(e) = Self::write(app, snapshot).await {
+            crate::error::report_background_error(app, "Failed to save sync profiles", e);
+        }
+    }
+
+    async fn write(app: &AppHandle, snapshot: &SchedulerSnapshot) -> Result<(), AppError> {
+        let path = Self::get_profiles_path(app)?;
+        let content = serde_json::to_string_pretty(snapshot)?;
+        write_atomic(&path, content.as_bytes(), "schedules file").await
+    }
+
+    /// Start every profile whose next run has come
+    async fn run_due(app: &AppHandle) {
+        let now = Local::now();
+        let due: Vec<u64> = {
+            let scheduler = app.state::<Scheduler>();
+            let state = scheduler.state.lock().await;
+            state
+                .profiles
+                .iter()
+                .filter(|profile| profile.is_due(&now))
+                .map(|profile| profile.id)
+                .collect()
+        };
+
+        for id in due {
+            let _ = Self::start(app, id).await;
+        }
+    }
+
+    /// Mark a profile as running and add its run to the transfer queue
+    async fn start(app: &AppHandle, id: u64) -> Result<(), AppError> {
+        let (config, origin) = {
+            let scheduler = app.state::<Scheduler>();
+            let mut state = scheduler.state.lock().await;
+            let profile = state
+                .profiles
+                .iter_mut()
+                .find(|profile| profile.id == id)
+                .ok_or(AppError::NotFound(format!("Sync profile {}", id)))?;
+
+            if profile.running {
+                return Err(AppError::InvalidRequest(format!(
+                    "Sync profile {} is already running",
+                    id
+                )));
+            }
+
+            profile.running = true;
+            profile.plan_next_run(&Local::now());
+            let config = profile.config.clone();
+            let origin = RunOrigin::Schedule {
+                profile_id: id,
+                profile_name: profile.name.clone(),
+            };
+            Self::save(app, &state).await;
+            (config, origin)
+        };
+
+        JobQueue::enqueue(app, config, Some(origin)).await;
+        Ok(())
+    }
+
+    /// Record the outcome of a profile's queued run
+    pub(super) async fn finish(
+        app: &AppHandle,
+        id: u64,
+        started_at: String,
+        result: Result<DownloadSummary, AppError>,
+    ) {
+        let (status, error) = match result {
+            Ok(summary) if summary.status == DownloadStatus::PartiallyFailed => (
+                ProfileRunStatus::PartiallyFailed,
+                Some(format!("{} file(s) failed to transfer", summary.errors)),
+            ),
+            Ok(_) => (ProfileRunStatus::Completed, None),
+            Err(AppError::Cancelled(_)) => (ProfileRunStatus::Cancelled, None),
+            Err(e) => (ProfileRunStatus::Failed, Some(e.to_string())),
+        };
+
+        let scheduler = app.state::<Scheduler>();
+        let mut state = scheduler.state.lock().await;
+        if let Some(profile) = state.profiles.iter_mut().find(|profile| profile.id == id) {
+            profile.running = false;
+            profile.runs.push(ProfileRun {
+                started_at,
+                finished_at: Local::now().to_rfc3339(),
+                status,
+                error,
+            });
+            let excess = profile.runs.len().saturating_sub(MAX_RECORDED_RUNS);
+            profile.runs.drain(..excess);
+        }
+        Self::save(app, &state).await;
+    }
+}
+
+#[tauri::command]
+pub async fn list_sync_profiles(state: State<'_, Scheduler>) -> Result<Vec<SyncProfile>, AppError> {
+    Ok(state.state.lock().await.profiles.clone())
+}
+
+/// Add a sync profile and return its id
+#[tauri::command]
+pub async fn create_sync_profile(
+    app: AppHandle,
+    state: State<'_, Scheduler>,
+    name: String,
+    mut config: DownloadConfig,
+    schedule: Schedule,
+    enabled: bool,
+) -> Result<u64, AppError> {
+    // Progress of a queued attempt is the queue's own, never the caller's
+    config.forget_progress();
+    config.validate()?;
+    schedule.validate()?;
+
+    let mut scheduler = state.state.lock().await;
+    let id = scheduler.next_id;
+    scheduler.next_id += 1;
+
+    let mut profile = SyncProfile {
+        id,
+        name,
+        config,
+        schedule,
+        enabled,
+        next_run: None,
+        running: false,
+        runs: Vec::new(),
+    };
+    profile.plan_next_run(&Local::now());
+    scheduler.profiles.push(profile);
+
+    Scheduler::save(&app, &scheduler).await;
+    Ok(id)
+}
+
+/// Replace the settings of a sync profile, keeping its past runs
+#[tauri::command]
+pub async fn update_sync_profile(
+    app: AppHandle,
+    state: State<'_, Scheduler>,
+    id: u64,
+    name: String,
+    mut config: DownloadConfig,
+    schedule: Schedule,
+    enabled: bool,
+) -> Result<(), AppError> {
+    // Progress of a queued attempt is the queue's own, never the caller's
+    config.forget_progress();
+    config.validate()?;
+    schedule.validate()?;
+
+    let mut scheduler = state.state.lock().await;
+    let profile = scheduler
+        .profiles
+        .iter_mut()
+        .find(|profile| profile.id == id)
+        .ok_or(AppError::NotFound(format!("Sync profile {}", id)))?;
+
+    profile.name = name;
+    profile.config = config;
+    profile.schedule = schedule;
+    profile.enabled = enabled;
+    profile.plan_next_run(&Local::now());
+
+    Scheduler::save(&app, &scheduler).await;
+    Ok(())
+}
+
+/// Remove a sync profile. A run in progress is left to finish.
+#[tauri::command]
+pub async fn delete_sync_profile(
+    app: AppHandle,
+    state: State<'_, Scheduler>,
+    id: u64,
+) -> Result<(), AppError> {
+    let mut scheduler = state.state.lock().await;
+    let index = scheduler
+        .profiles
+        .iter()
+        .position(|profile| profile.id == id)
+        .ok_or(AppError::NotFound(format!("Sync profile {}", id)))?;
+    scheduler.profiles.remove(index);
+
+    Scheduler::save(&app, &scheduler).await;
+    Ok(())
+}
+
+/// Start a sync profile now, without waiting for its schedule
+#[tauri::command]
+pub async fn run_sync_profile(app: AppHandle, id: u64) -> Result<(), AppError> {
+    Scheduler::start(&app, id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().to_utc()
+    }
+
+    #[test]
+    fn interval_runs_after_the_given_minutes() {
+        let schedule = Schedule::Interval { minutes: 90 };
+        assert_eq!(
+            schedule.next_after(&at("2026-01-05T10:00:00Z")),
+            Some(at("2026-01-05T11:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn daily_runs_at_the_next_matching_time() {
+        let every_day = Schedule::Daily {
+            hour: 3,
+            minute: 30,
+            weekdays: Vec::new(),
+        };
+        assert_eq!(
+            every_day.next_after(&at("2026-01-05T01:00:00Z")),
+            Some(at("2026-01-05T03:30:00Z"))
+        );
+        assert_eq!(
+            every_day.next_after(&at("2026-01-05T03:30:00Z")),
+            Some(at("2026-01-06T03:30:00Z"))
+        );
+
+        // 2026-01-05 is a Monday
+        let weekends = Schedule::Daily {
+            hour: 3,
+            minute: 30,
+            weekdays: vec![5, 6],
+        };
+        assert_eq!(
+            weekends.next_after(&at("2026-01-05T01:00:00Z")),
+            Some(at("2026-01-10T03:30:00Z"))
+        );
+        assert_eq!(
+            weekends.next_after(&at("2026-01-11T04:00:00Z")),
+            Some(at("2026-01-17T03:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn daily_time_is_local_to_the_time_zone() {
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let schedule = Schedule::Daily {
+            hour: 8,
+            minute: 0,
+            weekdays: Vec::new(),
+        };
+        let after = at("2026-01-05T00:00:00Z").with_timezone(&tokyo);
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next.to_utc(), at("2026-01-05T23:00:00Z"));
+    }
+
+    #[test]
+    fn invalid_schedules_are_rejected() {
+        assert!(Schedule::Interval { minutes: 0 }.validate().is_err());
+        assert!(
+            Schedule::Daily {
+                hour: 24,
+                minute: 0,
+                weekdays: Vec::new(),
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            Schedule::Daily {
+                hour: 1,
+                minute: 0,
+                weekdays: vec![7],
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn schedules_use_tagged_json() {
+        let schedule: Schedule = serde_json::from_str(
+            r#"{ "type": "daily", "hour": 3, "minute": 30, "weekdays": [0] }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            schedule,
+            Schedule::Daily {
+                hour: 3,
+                minute: 30,
+                weekdays: vec![0],
+            }
+        );
+
+        let schedule: Schedule =
+            serde_json::from_str(r#"{ "type": "interval", "minutes": 60 }"#).unwrap();
+        assert_eq!(schedule, Schedule::Interval { minutes: 60 });
+    }
+}
@@ -0,0 +1,410 @@
+use super::manifest;
+use super::types::GdriveFile;
+use crate::error::AppError;
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// Number of results returned when no limit is given
+const DEFAULT_SEARCH_LIMIT: usize = 200;
+
+/// What to look for in the archive. Unset filters match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchQuery {
+    /// Words to find in file names. Each word has to match, either as a
+    /// substring or with its letters in order, like `nggyu` for
+    /// `never_gonna_give_you_up`.
+    pub query: String,
+    /// File extensions without the dot, like `mp3`
+    pub extensions: Vec<String>,
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    /// Only search below this folder of the archive
+    pub folder: Option<String>,
+    /// A date like `2026-01-31` or a full RFC 3339 time
+    pub modified_after: Option<String>,
+    /// A date like `2026-01-31`, which includes that whole day, or a full
+    /// RFC 3339 time
+    pub modified_before: Option<String>,
+    /// Whether folders are searched too
+    pub include_folders: bool,
+    pub limit: Option<usize>,
+}
+
+/// A file matching a search, with how well it matches
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub file: GdriveFile,
+    pub score: u32,
+}
+
+/// The best matches of a search, best first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    /// Number of matches, including those past the limit
+    pub total: usize,
+    /// When the listing searched was fetched from Drive
+    pub refreshed_at: String,
+    /// Whether a newer listing is being fetched in the background
+    pub refreshing: bool,
+}
+
+/// A query with its filters parsed, ready to be matched against files
+struct Matcher {
+    terms: Vec<String>,
+    extensions: Vec<String>,
+    min_size: Option<i64>,
+    max_size: Option<i64>,
+    folder: Option<String>,
+    modified_after: Option<DateTime<Local>>,
+    modified_before: Option<DateTime<Local>>,
+    include_folders: bool,
+}
+
+/// Parse a date or time filter. A plain date stands for the start of that
+/// day, or the end of it when `end_of_day` is set.
+fn parse_date(value: &str, end_of_day: bool) -> Result<DateTime<Local>, AppError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Local));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidRequest(format!("Invalid date: {}", value)))?;
+    let date = if end_of_day {
+        date.succ_opt().unwrap_or(date)
+    } else {
+        date
+    };
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .ok_or_else(|| AppError::InvalidRequest(format!("Invalid date: {}", value)))
+}
+
+impl Matcher {
+    fn new(query: &SearchQuery) -> Result<Self, AppError> {
+        if let (Some(min), Some(max)) = (query.min_size, query.max_size)
+            && min > max
+        {
+            return Err(AppError::InvalidRequest(
+                "Minimum size is larger than the maximum size".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            terms: query
+                .query
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .collect(),
+            extensions: query
+                .extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .filter(|ext| !ext.is_empty())
+                .collect(),
+            min_size: query.min_size,
+            max_size: query.max_size,
+            folder: query
+                .folder
+                .as_deref()
+                .map(|folder| folder.trim_matches('/'))
+                .filter(|folder| !folder.is_empty())
+                .map(|folder| format!("{}/", folder)),
+            modified_after: query
+                .modified_after
+                .as_deref()
+                .map(|date| parse_date(date, false))
+                .transpose()?,
+            modified_before: query
+                .modified_before
+                .as_deref()
+                .map(|date| parse_date(date, true))
+                .transpose()?,
+            include_folders: query.include_folders,
+        })
+    }
+
+    /// Whether a file passes the filters, regardless of its name
+    fn filters(&self, file: &GdriveFile) -> bool {
+        if file.is_dir {
+            // Extensions and sizes only apply to files
+            let file_filters =
+                !self.extensions.is_empty() || self.min_size.is_some() || self.max_size.is_some();
+            if !self.include_folders || file_filters {
+                return false;
+            }
+        }
+        if let Some(folder) = &self.folder
+            && !file.path.starts_with(folder.as_str())
+        {
+            return false;
+        }
+        if !self.extensions.is_empty() {
+            let extension = file
+                .name
+                .rsplit_once('.')
+                .map(|(_, ext)| ext.to_lowercase());
+            if !extension.is_some_and(|ext| self.extensions.contains(&ext)) {
+                return false;
+            }
+        }
+        // Google Docs have no size, so they never match a size filter
+        if self.min_size.is_some_and(|min| file.size < min.max(0))
+            || self
+                .max_size
+                .is_some_and(|max| file.size < 0 || file.size > max)
+        {
+            return false;
+        }
+        if self.modified_after.is_some() || self.modified_before.is_some() {
+            let Some(modified) = file
+                .mod_time
+                .as_deref()
+                .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            else {
+                return false;
+            };
+            if self.modified_after.is_some_and(|after| modified < after)
+                || self
+                    .modified_before
+                    .is_some_and(|before| modified >= before)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// How well a file matches the search words, or `None` when it doesn't
+    fn score(&self, file: &GdriveFile) -> Option<u32> {
+        let name = file.name.to_lowercase();
+        let path = file.path.to_lowercase();
+        self.terms
+            .iter()
+            .map(|term| term_score(term, &name, &path))
+            .sum()
+    }
+
+    /// The matching files with their scores, best first. The files are
+    /// borrowed, so only the ones returned have to be copied.
+    fn search<'a>(&self, files: &'a [GdriveFile]) -> Vec<(&'a GdriveFile, u32)> {
+        let mut hits: Vec<(&GdriveFile, u32)> = files
+            .iter()
+            .filter(|file| self.filters(file))
+            .filter_map(|file| Some((file, self.score(file)?)))
+            .collect();
+
+        // Shorter names are closer to what was typed
+        hits.sort_by(|(a, a_score), (b, b_score)| {
+            b_score
+                .cmp(a_score)
+                .then(a.name.len().cmp(&b.name.len()))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        hits
+    }
+}
+
+/// Score a single search word against a lowercase name and path
+fn term_score(term: &str, name: &str, path: &str) -> Option<u32> {
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    if name == term || stem == term {
+        return Some(1000);
+    }
+    if name.starts_with(term) {
+        return Some(800);
+    }
+    if let Some(position) = name.find(term) {
+        let word_start = name[..position]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_alphanumeric());
+        if word_start {
+            return Some(600);
+        }
+        // Earlier matches rank higher
+        return Some(500 - position.min(100) as u32);
+    }
+    if path.contains(term) {
+        return Some(300);
+    }
+    fuzzy_score(term, name)
+}
+
+/// Score a name containing the letters of a word in order, ranking those
+/// with the letters closer together higher
+fn fuzzy_score(term: &str, name: &str) -> Option<u32> {
+    let mut chars = name.char_indices();
+    let mut first = None;
+    let mut last = 0;
+    for wanted in term.chars() {
+        let (index, _) = chars.find(|(_, c)| *c == wanted)?;
+        first.get_or_insert(index);
+        last = index;
+    }
+    let span = last - first.unwrap_or(0) + 1;
+    let gaps = span.saturating_sub(term.len());
+    Some(200u32.saturating_sub(gaps as u32 * 5).max(1))
+}
+
+/// Search the cached listing of a Drive folder by name, extension, size,
+/// folder and modification time, best matches first. The listing is kept in
+/// memory between searches. Without a cached listing the folder is listed
+/// on Drive first.
+#[tauri::command]
+pub async fn search_archive(
+    app: AppHandle,
+    source: String,
+    remote_config: String,
+    query: SearchQuery,
+) -> Result<SearchResults, AppError> {
+    let matcher = Matcher::new(&query)?;
+    let (loaded, refreshing) = manifest::load(&app, &source, &remote_config).await?;
+
+    let matches = matcher.search(&loaded.manifest.entries);
+    let total = matches.len();
+    let hits = matches
+        .into_iter()
+        .take(query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .map(|(file, score)| SearchHit {
+            file: file.clone(),
+            score,
+        })
+        .collect();
+
+    Ok(SearchResults {
+        hits,
+        total,
+        refreshed_at: loaded.manifest.refreshed_at.clone(),
+        refreshing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: i64, mod_time: &str) -> GdriveFile {
+        GdriveFile {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap().to_string(),
+            size,
+            mod_time: Some(mod_time.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn archive() -> Vec<GdriveFile> {
+        vec![
+            file(
+                "2025/Never Gonna Give You Up.mp3",
+                5_000,
+                "2025-03-01T12:00:00Z",
+            ),
+            file("2025/Give.flac", 30_000, "2025-06-01T12:00:00Z"),
+            file("2026/Forgive Me.mp3", 4_000, "2026-01-15T12:00:00Z"),
+            file("2026/Gimme Love.mp3", 3_000, "2026-02-01T12:00:00Z"),
+            file("2026/cover.jpg", 100, "2026-02-01T12:00:00Z"),
+            GdriveFile {
+                is_dir: true,
+                ..file("2026/Give", 0, "2026-02-01T12:00:00Z")
+            },
+        ]
+    }
+
+    fn search(query: SearchQuery) -> Vec<String> {
+        Matcher::new(&query)
+            .unwrap()
+            .search(&archive())
+            .into_iter()
+            .map(|(file, _)| file.path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn names_are_ranked_by_how_well_they_match() {
+        let hits = search(SearchQuery {
+            query: "give".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            hits,
+            [
+                "2025/Give.flac",
+                "2025/Never Gonna Give You Up.mp3",
+                "2026/Forgive Me.mp3",
+                // Matches with the letters spread out
+                "2026/Gimme Love.mp3",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_word_has_to_match() {
+        let hits = search(SearchQuery {
+            query: "gonna UP".to_string(),
+            include_folders: true,
+            ..Default::default()
+        });
+
+        assert_eq!(hits, ["2025/Never Gonna Give You Up.mp3"]);
+    }
+
+    #[test]
+    fn filters_narrow_down_the_results() {
+        let by_extension = search(SearchQuery {
+            extensions: vec![".MP3".to_string()],
+            folder: Some("/2026/".to_string()),
+            ..Default::default()
+        });
+        let by_size = search(SearchQuery {
+            min_size: Some(4_000),
+            max_size: Some(10_000),
+            ..Default::default()
+        });
+        let by_date = search(SearchQuery {
+            modified_after: Some("2025-06-01".to_string()),
+            modified_before: Some("2026-01-15".to_string()),
+            ..Default::default()
+        });
+        let folders = search(SearchQuery {
+            query: "give".to_string(),
+            folder: Some("2026".to_string()),
+            include_folders: true,
+            ..Default::default()
+        });
+
+        assert_eq!(by_extension, ["2026/Forgive Me.mp3", "2026/Gimme Love.mp3"]);
+        assert_eq!(
+            by_size,
+            ["2026/Forgive Me.mp3", "2025/Never Gonna Give You Up.mp3"]
+        );
+        assert_eq!(by_date, ["2025/Give.flac", "2026/Forgive Me.mp3"]);
+        assert_eq!(
+            folders,
+            ["2026/Give", "2026/Forgive Me.mp3", "2026/Gimme Love.mp3"]
+        );
+    }
+
+    #[test]
+    fn invalid_filters_are_rejected() {
+        let bad_date = SearchQuery {
+            modified_after: Some("last week".to_string()),
+            ..Default::default()
+        };
+        let bad_sizes = SearchQuery {
+            min_size: Some(10),
+            max_size: Some(1),
+            ..Default::default()
+        };
+
+        assert!(Matcher::new(&bad_date).is_err());
+        assert!(Matcher::new(&bad_sizes).is_err());
+    }
+}
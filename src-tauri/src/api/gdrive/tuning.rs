@@ -0,0 +1,525 @@
+use crate::api::rclone;
+use crate::error::AppError;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Weekday};
+use rclone_sdk::Client;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, OnceLock};
+use tauri::AppHandle;
+use tokio::sync::Mutex;
+
+/// How often a job with a bandwidth timetable is checked on
+const SCHEDULE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Downloads running with their own bandwidth limit
+static ACTIVE_LIMITS: LazyLock<Mutex<ActiveLimits>> = LazyLock::new(Default::default);
+
+/// Limits and parallelism for a download. Unset options keep rclone's
+/// defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransferOptions {
+    /// A rate like `512k`, `off`, `1M:256k` for separate download and upload
+    /// limits, or a timetable like `08:00,512k 23:00,off`, as taken by
+    /// rclone's `--bwlimit`
+    pub bw_limit: Option<String>,
+    /// Number of files transferred in parallel
+    pub transfers: Option<u32>,
+    /// Number of files checked in parallel
+    pub checkers: Option<u32>,
+    /// Size of the chunks Google Drive transfers in, like `64M`
+    pub chunk_size: Option<String>,
+}
+
+impl TransferOptions {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(limit) = &self.bw_limit {
+            parse_bw_limit(limit)?;
+        }
+        if self.transfers == Some(0) || self.checkers == Some(0) {
+            return Err(AppError::InvalidRequest(
+                "Transfers and checkers must be at least 1".to_string(),
+            ));
+        }
+        if let Some(size) = &self.chunk_size
+            && !is_size(size)
+        {
+            return Err(AppError::InvalidRequest(format!(
+                "Invalid chunk size: {}",
+                size
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The live bandwidth limit reported by rclone
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthLimit {
+    pub rate: String,
+    /// Download limit, or -1 when there is none
+    pub bytes_per_second: i64,
+}
+
+/// A point in a bandwidth timetable where the limit changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BandwidthChange {
+    /// The day the change happens on, or every day when unset
+    weekday: Option<Weekday>,
+    time: NaiveTime,
+    /// The rate from then on
+    rate: String,
+}
+
+impl BandwidthChange {
+    /// The last time at or before the given one the change happened
+    fn last_at_or_before<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        (0..=7).find_map(|offset| {
+            let date = at.date_naive() - Duration::days(offset);
+            if self.weekday.is_some_and(|day| day != date.weekday()) {
+                return None;
+            }
+            let time = at
+                .timezone()
+                .from_local_datetime(&date.and_time(self.time))
+                .earliest()?;
+            (time <= *at).then_some(time)
+        })
+    }
+
+    /// The first time after the given one the change happens
+    fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        (0..=7).find_map(|offset| {
+            let date = after.date_naive() + Duration::days(offset);
+            if self.weekday.is_some_and(|day| day != date.weekday()) {
+                return None;
+            }
+            let time = after
+                .timezone()
+                .from_local_datetime(&date.and_time(self.time))
+                .earliest()?;
+            (time > *after).then_some(time)
+        })
+    }
+}
+
+/// Whether the text is a size like `512k`, `1.5M` or `10Mi`
+fn is_size(text: &str) -> bool {
+    static SIZE_RE: OnceLock<Regex> = OnceLock::new();
+    SIZE_RE
+        .get_or_init(|| Regex::new(r"(?i)^\d+(\.\d+)?(b|[kmgtp]i?)?$").unwrap())
+        .is_match(text)
+}
+
+/// Whether the text is a single rate, optionally split into download and
+/// upload limits
+fn is_rate(text: &str) -> bool {
+    let mut parts = text.split(':');
+    let valid = |part: Option<&str>| part.is_some_and(|part| part == "off" || is_size(part));
+    match (parts.next(), parts.next(), parts.next()) {
+        (down, None, None) => valid(down),
+        (down, up, None) => valid(down) && valid(up),
+        _ => false,
+    }
+}
+
+/// Check a bandwidth limit and return the times it changes, which is none
+/// for a single rate
+fn parse_bw_limit(limit: &str) -> Result<Vec<BandwidthChange>, AppError> {
+    let invalid = || AppError::InvalidRequest(format!("Invalid bandwidth limit: {}", limit));
+    let entries: Vec<&str> = limit.split_whitespace().collect();
+
+    if let [rate] = entries.as_slice()
+        && !rate.contains(',')
+    {
+        return if is_rate(rate) {
+            Ok(Vec::new())
+        } else {
+            Err(invalid())
+        };
+    }
+    if entries.is_empty() {
+        return Err(invalid());
+    }
+
+    entries
+        .iter()
+        .map(|entry| {
+            let (start, rate) = entry.split_once(',').ok_or_else(invalid)?;
+            if !is_rate(rate) {
+                return Err(invalid());
+            }
+            let (weekday, time) = match start.split_once('-') {
+                Some((day, time)) => (Some(day.parse().map_err(|_| invalid())?), time),
+                None => (None, start),
+            };
+            let time = NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| invalid())?;
+            Ok(BandwidthChange {
+                weekday,
+                time,
+                rate: rate.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The next time any of the changes happens
+fn next_change<Tz: TimeZone>(
+    changes: &[BandwidthChange],
+    after: &DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+    changes
+        .iter()
+        .filter_map(|change| change.next_after(after))
+        .min()
+}
+
+/// The rate a bandwidth limit sets at the given time: the limit itself for
+/// a single rate, or the timetable slot that started last
+fn rate_at<Tz: TimeZone>(limit: &str, changes: &[BandwidthChange], at: &DateTime<Tz>) -> String {
+    if changes.is_empty() {
+        return limit.trim().to_string();
+    }
+    changes
+        .iter()
+        .filter_map(|change| Some((change.last_at_or_before(at)?, &change.rate)))
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map_or_else(|| "off".to_string(), |(_, rate)| rate.clone())
+}
+
+async fn set_rate(client: &Client, rate: &str) -> Result<BandwidthLimit, AppError> {
+    let response = client
+        .core_bwlimit(None, None, Some(rate))
+        .await?
+        .into_inner();
+    Ok(BandwidthLimit {
+        rate: response.rate,
+        bytes_per_second: response.bytes_per_second,
+    })
+}
+
+/// The limit of a download that set one, until it is released
+pub(super) struct LimitLease(u64);
+
+/// A download's bandwidth limit, parsed into the times it changes
+struct ActiveLimit {
+    id: u64,
+    limit: String,
+    changes: Vec<BandwidthChange>,
+}
+
+impl ActiveLimit {
+    fn rate(&self) -> String {
+        rate_at(&self.limit, &self.changes, &Local::now())
+    }
+}
+
+/// rclone keeps a single limit for all transfers. The newest download that
+/// set a limit decides it, unless a limit was set live since, and once none
+/// is left the limit from before the first of them is restored.
+#[derive(Default)]
+struct ActiveLimits {
+    next_id: u64,
+    /// Oldest first
+    limits: Vec<ActiveLimit>,
+    /// The rate in effect before the first download set its own
+    previous: Option<String>,
+    /// A limit set live while downloads ran with their own, which takes
+    /// their place and is kept once they end
+    live: Option<ActiveLimit>,
+}
+
+impl ActiveLimits {
+    /// The limit that decides the rate while downloads run with their own
+    fn effective(&self) -> Option<&ActiveLimit> {
+        self.live.as_ref().or(self.limits.last())
+    }
+}
+
+/// Apply a download's bandwidth limit before its job is submitted. It is set
+/// on the server rather than in the job's own config, one timetable slot at
+/// a time, since rclone only applies the current slot of a timetable set
+/// over the API. The lease has to be released once the job ends.
+pub(super) async fn apply_bandwidth_limit(
+    client: &Client,
+    options: &TransferOptions,
+) -> Result<Option<LimitLease>, AppError> {
+    let Some(limit) = &options.bw_limit else {
+        return Ok(None);
+    };
+    let changes = parse_bw_limit(limit)?;
+
+    let mut active = ACTIVE_LIMITS.lock().await;
+    if active.limits.is_empty() {
+        let current = client.core_bwlimit(None, None, None).await?.into_inner();
+        active.previous = Some(current.rate);
+    }
+    let id = active.next_id;
+    active.next_id += 1;
+    let limit = ActiveLimit {
+        id,
+        limit: limit.clone(),
+        changes,
+    };
+    set_rate(client, &limit.rate()).await?;
+    active.limits.push(limit);
+    Ok(Some(LimitLease(id)))
+}
+
+/// Drop a download's limit, going back to the limit of the newest download
+/// still running with one, or to the limit from before any of them
+pub(super) async fn release_bandwidth_limit(client: &Client, lease: LimitLease) {
+    let mut active = ACTIVE_LIMITS.lock().await;
+    let Some(index) = active.limits.iter().position(|limit| limit.id == lease.0) else {
+        return;
+    };
+    let was_newest = index + 1 == active.limits.len();
+    active.limits.remove(index);
+
+    let rate = match active.effective() {
+        None => active.previous.take().unwrap_or_else(|| "off".to_string()),
+        // A download ending leaves a limit set live in place
+        _ if active.live.is_some() && !active.limits.is_empty() => return,
+        // An older download ending leaves the newest one's limit in place
+        Some(_) if !was_newest => return,
+        Some(effective) => effective.rate(),
+    };
+    if active.limits.is_empty() {
+        active.previous = None;
+        active.live = None;
+    }
+    let _ = set_rate(client, &rate).await;
+}
+
+/// Follow a download's bandwidth timetable while its job runs and release
+/// its limit once the job finishes
+pub(super) fn spawn_bandwidth_schedule(
+    app: &AppHandle,
+    client: &Client,
+    jobid: i64,
+    lease: Option<LimitLease>,
+) {
+    let Some(lease) = lease else {
+        return;
+    };
+    let app = app.clone();
+    let client = client.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut next = next_change_of(&lease).await;
+        loop {
+            tokio::time::sleep(SCHEDULE_INTERVAL).await;
+
+            let finished = match client.job_status(None, jobid as f64).await {
+                Ok(status) => status.into_inner().finished,
+                Err(_) => true,
+            };
+            if finished {
+                release_bandwidth_limit(&client, lease).await;
+                break;
+            }
+
+            if next.is_some_and(|next| next <= Local::now()) {
+                if let Err(e) = apply_current_slot(&client, &lease).await {
+                    crate::error::report_background_error(
+                        &app,
+                        "Failed to update bandwidth limit",
+                        e,
+                    );
+                }
+                next = next_change_of(&lease).await;
+            }
+        }
+    });
+}
+
+/// The next change of the limit in effect, or of the download's own limit
+/// while an other one is in effect
+async fn next_change_of(lease: &LimitLease) -> Option<DateTime<Local>> {
+    let active = ACTIVE_LIMITS.lock().await;
+    let limit = match active.limits.last() {
+        Some(newest) if newest.id == lease.0 => active.effective()?,
+        _ => active.limits.iter().find(|limit| limit.id == lease.0)?,
+    };
+    next_change(&limit.changes, &Local::now())
+}
+
+/// Set the current slot of the limit in effect, if the download is the
+/// newest one with a limit
+async fn apply_current_slot(client: &Client, lease: &LimitLease) -> Result<(), AppError> {
+    let active = ACTIVE_LIMITS.lock().await;
+    if let Some(newest) = active.limits.last()
+        && newest.id == lease.0
+        && let Some(effective) = active.effective()
+    {
+        set_rate(client, &effective.rate()).await?;
+    }
+    Ok(())
+}
+
+/// Set a limit live. While downloads run with their own limits it takes
+/// their place, so neither their timetables nor their end undo it.
+async fn set_live_limit(client: &Client, limit: &str) -> Result<BandwidthLimit, AppError> {
+    let changes = parse_bw_limit(limit)?;
+    let mut active = ACTIVE_LIMITS.lock().await;
+    let limit = ActiveLimit {
+        id: active.next_id,
+        limit: limit.to_string(),
+        changes,
+    };
+    let applied = set_rate(client, &limit.rate()).await?;
+    if !active.limits.is_empty() {
+        active.next_id += 1;
+        active.live = Some(limit);
+    }
+    Ok(applied)
+}
+
+/// Change the bandwidth limit of running transfers, until it is changed
+/// again. Only the current slot of a timetable is applied.
+#[tauri::command]
+pub async fn set_bandwidth_limit(app: AppHandle, rate: String) -> Result<BandwidthLimit, AppError> {
+    let client = rclone::get_sdk_client(&app).await?;
+    set_live_limit(&client, &rate).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rclone::mock::MockRcServer;
+    use chrono::Utc;
+
+    #[test]
+    fn bandwidth_limits_are_validated() {
+        assert_eq!(parse_bw_limit("512k").unwrap(), []);
+        assert_eq!(parse_bw_limit("off").unwrap(), []);
+        assert_eq!(parse_bw_limit("1.5M:256Ki").unwrap(), []);
+
+        let changes = parse_bw_limit("08:00,512k 23:00,off Sat-10:30,10M").unwrap();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[2].weekday, Some(Weekday::Sat));
+        assert_eq!(changes[2].time, NaiveTime::from_hms_opt(10, 30, 0).unwrap());
+
+        for invalid in ["", "fast", "512x", "08:00,", "25:00,1M", "Someday-08:00,1M"] {
+            assert!(parse_bw_limit(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn next_change_follows_the_timetable() {
+        let changes = parse_bw_limit("08:00,512k 23:00,off Sat-10:30,10M").unwrap();
+        // A Friday
+        let friday = Utc.with_ymd_and_hms(2026, 1, 2, 9, 0, 0).unwrap();
+
+        assert_eq!(
+            next_change(&changes, &friday),
+            Some(Utc.with_ymd_and_hms(2026, 1, 2, 23, 0, 0).unwrap())
+        );
+        let saturday = Utc.with_ymd_and_hms(2026, 1, 3, 9, 0, 0).unwrap();
+        assert_eq!(
+            next_change(&changes, &saturday),
+            Some(Utc.with_ymd_and_hms(2026, 1, 3, 10, 30, 0).unwrap())
+        );
+        assert_eq!(next_change(&[], &friday), None);
+    }
+
+    #[test]
+    fn options_are_validated() {
+        assert!(TransferOptions::default().validate().is_ok());
+        let options = TransferOptions {
+            bw_limit: Some("08:00,512k 23:00,off".to_string()),
+            transfers: Some(8),
+            checkers: Some(16),
+            chunk_size: Some("64M".to_string()),
+        };
+        assert!(options.validate().is_ok());
+
+        let zero = TransferOptions {
+            transfers: Some(0),
+            ..Default::default()
+        };
+        assert!(zero.validate().is_err());
+        let chunk = TransferOptions {
+            chunk_size: Some("big".to_string()),
+            ..Default::default()
+        };
+        assert!(chunk.validate().is_err());
+    }
+
+    #[test]
+    fn timetables_send_their_current_slot() {
+        let limit = "08:00,512k 23:00,off Sat-10:30,10M";
+        let changes = parse_bw_limit(limit).unwrap();
+        let at = |day, hour| Utc.with_ymd_and_hms(2026, 1, day, hour, 0, 0).unwrap();
+
+        // Friday morning, Friday night and Saturday after 10:30
+        assert_eq!(rate_at(limit, &changes, &at(2, 9)), "512k");
+        assert_eq!(rate_at(limit, &changes, &at(2, 23)), "off");
+        assert_eq!(rate_at(limit, &changes, &at(3, 11)), "10M");
+        // Before 08:00 the slot started the night before
+        assert_eq!(rate_at(limit, &changes, &at(2, 7)), "off");
+        assert_eq!(rate_at(" 512k ", &[], &at(2, 7)), "512k");
+    }
+
+    #[tokio::test]
+    async fn limits_are_set_on_the_server_and_restored() {
+        let server = MockRcServer::start().await;
+        server.state().bw_limit = Some("10M".to_string());
+        let client = server.client();
+        let options = |limit: &str| TransferOptions {
+            bw_limit: Some(limit.to_string()),
+            ..Default::default()
+        };
+        let rates = || -> Vec<String> {
+            server
+                .state()
+                .calls
+                .iter()
+                .filter(|(path, _)| path == "core/bwlimit")
+                .filter_map(|(_, params)| params.get("rate")?.as_str().map(str::to_string))
+                .collect()
+        };
+
+        let none = apply_bandwidth_limit(&client, &TransferOptions::default())
+            .await
+            .unwrap();
+        assert!(none.is_none());
+
+        let first = apply_bandwidth_limit(&client, &options("512k"))
+            .await
+            .unwrap()
+            .unwrap();
+        // Only the current slot of a timetable is sent
+        let second = apply_bandwidth_limit(&client, &options("00:00,1M"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rates(), ["512k", "1M"]);
+
+        // The newest download's limit stays while it runs
+        release_bandwidth_limit(&client, first).await;
+        assert_eq!(rates(), ["512k", "1M"]);
+
+        // The last one restores the limit from before, not `off`
+        release_bandwidth_limit(&client, second).await;
+        assert_eq!(rates(), ["512k", "1M", "10M"]);
+        assert_eq!(server.state().bw_limit.as_deref(), Some("10M"));
+
+        // A limit set live outlasts the timetable and the end of the
+        // download that was running
+        let third = apply_bandwidth_limit(&client, &options("00:00,1M"))
+            .await
+            .unwrap()
+            .unwrap();
+        set_live_limit(&client, "2M").await.unwrap();
+        apply_current_slot(&client, &third).await.unwrap();
+        release_bandwidth_limit(&client, third).await;
+        assert_eq!(rates()[3..], ["1M", "2M", "2M", "2M"]);
+        assert_eq!(server.state().bw_limit.as_deref(), Some("2M"));
+
+        // Without any download running it is simply set
+        set_live_limit(&client, "off").await.unwrap();
+        assert!(ACTIVE_LIMITS.lock().await.live.is_none());
+    }
+}
@@ -1,4 +1,9 @@
-#[derive(serde::Serialize, Clone, Debug)]
+use std::collections::BTreeMap;
+
+/// Mime type prefix of documents native to Google Drive, like Docs and Sheets
+const GOOGLE_APPS_MIME_PREFIX: &str = "application/vnd.google-apps.";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct GdriveFile {
     pub path: String,
@@ -6,4 +11,83 @@ pub struct GdriveFile {
     pub is_dir: bool,
     pub size: i64,
     pub mime_type: String,
+    pub mod_time: Option<String>,
+    /// When the file was created on Drive, if known
+    #[serde(default)]
+    pub created_time: Option<String>,
+    /// Hashes Drive keeps for the file, keyed by type, like `md5`
+    #[serde(default)]
+    pub hashes: BTreeMap<String, String>,
+    /// Drive's id for the file, or for the target of a shortcut
+    pub id: Option<String>,
+    /// The shortcut's own id, when the entry is a shortcut to another file
+    #[serde(default)]
+    pub shortcut_id: Option<String>,
+    /// A Google Docs, Sheets or similar document, which has no file contents
+    /// and can only be downloaded converted to another format
+    #[serde(default)]
+    pub is_google_doc: bool,
+}
+
+impl GdriveFile {
+    pub fn md5(&self) -> Option<&str> {
+        self.hashes.get("md5").map(String::as_str)
+    }
+
+    pub fn is_shortcut(&self) -> bool {
+        self.shortcut_id.is_some()
+    }
+}
+
+/// Whether a listed file is a Google-native document. rclone lists those
+/// with their export format and an unknown size.
+pub(super) fn is_google_doc(mime_type: &str, size: i64, is_dir: bool) -> bool {
+    !is_dir && (mime_type.starts_with(GOOGLE_APPS_MIME_PREFIX) || size < 0)
+}
+
+/// Split an id listed by rclone into the shortcut's own id, if it is one,
+/// and the id of the file it points to. rclone joins them with a tab,
+/// target first.
+pub(super) fn split_shortcut_id(id: &str) -> (Option<String>, String) {
+    match id.split_once('\t') {
+        Some((target, shortcut)) => (Some(shortcut.to_string()), target.to_string()),
+        None => (None, id.to_string()),
+    }
+}
+
+/// The files below a Drive folder and how current the listing is
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GdriveListing {
+    pub files: Vec<GdriveFile>,
+    /// When the listing was fetched from Drive
+    pub refreshed_at: String,
+    /// Whether a newer listing is being fetched in the background
+    pub refreshing: bool,
+}
+
+/// A file or folder directly inside a listed folder
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderEntry {
+    #[serde(flatten)]
+    pub file: GdriveFile,
+    /// Entries directly inside a folder, when its contents are known
+    pub child_count: Option<usize>,
+    /// Size of every file below a folder, when its contents are known
+    pub total_size: Option<i64>,
+}
+
+/// A page of the entries directly inside a folder
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderPage {
+    pub path: String,
+    pub entries: Vec<FolderEntry>,
+    /// Number of entries in the folder across all pages
+    pub total: usize,
+    pub offset: usize,
+    /// When the cached listing the page came from was fetched, if it came
+    /// from one
+    pub refreshed_at: Option<String>,
 }
@@ -1,3 +1,6 @@
+use crate::error::AppError;
+use std::path::Path;
+
 /// Parse a Google Drive ID from various URL formats or raw ID
 pub fn parse_gdrive_id(source: &str) -> String {
     // Handle full URLs like https://drive.google.com/drive/folders/12345...
@@ -22,3 +25,25 @@ pub fn parse_gdrive_id(source: &str) -> String {
     // Assume it's an ID if no known prefix found
     source.to_string()
 }
+
+/// Replace a file with new content without leaving it half written if the
+/// app stops midway: the content goes to a temporary file next to it first,
+/// which is then renamed over it
+pub(super) async fn write_atomic(path: &Path, content: &[u8], what: &str) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| AppError::io(&format!("Failed to create {} dir", what), e))?;
+    }
+
+    // Unique, as two writes of the same file can overlap
+    let temp = path.with_extension(format!("{:08x}.tmp", rand::random::<u32>()));
+    tokio::fs::write(&temp, content)
+        .await
+        .map_err(|e| AppError::io(&format!("Failed to write {}", what), e))?;
+    if let Err(e) = tokio::fs::rename(&temp, path).await {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(AppError::io(&format!("Failed to replace {}", what), e));
+    }
+    Ok(())
+}
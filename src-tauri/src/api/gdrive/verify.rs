@@ -0,0 +1,238 @@
+use super::download::{self, CancelledJobs, DownloadConfig, FilesystemPaths, JobOutcome};
+use crate::api::rclone;
+use crate::error::AppError;
+use rclone_sdk::ClientInfo;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How the local archive compares to the files on Drive
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyResult {
+    /// Hash the files were compared by, when Drive and the disk share one
+    pub hash_type: Option<String>,
+    /// Files on Drive that aren't in the archive
+    pub missing: Vec<String>,
+    /// Files in the archive that aren't on Drive
+    pub extra: Vec<String>,
+    /// Files whose contents don't match Drive
+    pub differing: Vec<String>,
+    /// Files that couldn't be compared
+    pub errors: Vec<String>,
+    /// Differing files that were downloaded again
+    pub redownloaded: Vec<String>,
+}
+
+/// Build the `operations/check` request comparing Drive with the archive
+fn build_check_body(config: &DownloadConfig, paths: &FilesystemPaths) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "_async": true,
+        "srcFs": paths.src_fs,
+        "dstFs": paths.dst_fs,
+        "missingOnDst": true,
+        "missingOnSrc": true,
+        "differ": true,
+        "error": true
+    });
+    if let Some(files) = config.selected_files() {
+        body["_filter"] = download::build_file_filter(files);
+    }
+    body
+}
+
+/// Run a check job until it finishes and read its report
+async fn run_check(
+    app: &AppHandle,
+    client: &rclone_sdk::Client,
+    body: &serde_json::Value,
+) -> Result<VerifyResult, AppError> {
+    let jobid = download::submit_sync_job(client, body, "/operations/check").await?;
+    let _ = app.emit("verify-job-started", jobid);
+    let cancelled = app.state::<CancelledJobs>();
+    cancelled.track(jobid).await;
+    rclone::spawn_job_monitor(app, client, jobid);
+
+    check_result(client, &cancelled, jobid).await
+}
+
+/// Wait for a check job and turn its output into a result. Differences
+/// don't fail the job, they are only reported in its output.
+async fn check_result(
+    client: &rclone_sdk::Client,
+    cancelled: &CancelledJobs,
+    jobid: i64,
+) -> Result<VerifyResult, AppError> {
+    match download::poll_job_completion(client, cancelled, jobid).await? {
+        JobOutcome::Completed => {}
+        JobOutcome::Cancelled => {
+            return Err(AppError::Cancelled("Verification cancelled".to_string()));
+        }
+        JobOutcome::Interrupted => return Err(AppError::Interrupted),
+    }
+
+    let output = client
+        .job_status(None, jobid as f64)
+        .await?
+        .into_inner()
+        .output
+        .unwrap_or_default();
+    let report: rclone_sdk::types::OperationsCheckResponse = serde_json::from_value(output)
+        .map_err(|e| AppError::Internal(format!("Failed to parse check result: {}", e)))?;
+
+    Ok(VerifyResult {
+        hash_type: report.hash_type.filter(|hash| !hash.is_empty()),
+        missing: report.missing_on_dst,
+        extra: report.missing_on_src,
+        differing: report.differ,
+        errors: report.error,
+        redownloaded: Vec::new(),
+    })
+}
+
+/// Download the given files again, even where their size and time match
+async fn redownload(
+    client: &rclone_sdk::Client,
+    paths: &FilesystemPaths,
+    files: &[String],
+) -> Result<Vec<String>, AppError> {
+    let mut redownloaded = Vec::new();
+    for file in files {
+        let response = client
+            .client()
+            .post(format!("{}/operations/copyfile", client.baseurl()))
+            .json(&serde_json::json!({
+                "srcFs": paths.src_fs,
+                "srcRemote": file,
+                "dstFs": paths.dst_fs,
+                "dstRemote": file,
+                "_config": { "IgnoreTimes": true }
+            }))
+            .send()
+            .await
+            .map_err(|e| AppError::request("Download failed", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let err_text = response.text().await.unwrap_or_default();
+            return Err(AppError::from_rc_body(status, &err_text));
+        }
+        redownloaded.push(file.clone());
+    }
+    Ok(redownloaded)
+}
+
+/// Compare the archive with Drive by checksum, optionally downloading files
+/// that don't match again
+#[tauri::command]
+pub async fn verify_archive(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    remote_config: Option<String>,
+    create_subfolder: bool,
+    selected_files: Option<Vec<String>>,
+    redownload_corrupt: bool,
+) -> Result<VerifyResult, AppError> {
+    let config = DownloadConfig::new(
+        source,
+        destination,
+        remote_config,
+        false,
+        create_subfolder,
+        selected_files,
+        false,
+        false,
+        false,
+    )?;
+    let paths = config.build_filesystem_paths()?;
+    let client = rclone::get_sdk_client(&app).await?;
+
+    let mut result = run_check(&app, &client, &build_check_body(&config, &paths)).await?;
+    if redownload_corrupt {
+        result.redownloaded = redownload(&client, &paths, &result.differing).await?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rclone::mock::MockRcServer;
+    use serde_json::json;
+
+    fn config() -> DownloadConfig {
+        DownloadConfig::new(
+            "FOLDER_ID".to_string(),
+            "/music".to_string(),
+            Some("gdrive".to_string()),
+            false,
+            false,
+            Some(vec!["album".to_string()]),
+            false,
+            false,
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn check_compares_both_ways_within_the_selection() {
+        let config = config();
+        let paths = config.build_filesystem_paths().unwrap();
+
+        let body = build_check_body(&config, &paths);
+
+        assert_eq!(body["srcFs"], "gdrive,root_folder_id=FOLDER_ID:");
+        assert_eq!(body["_async"], true);
+        assert_eq!(body["missingOnSrc"], true);
+        assert_eq!(body["missingOnDst"], true);
+        assert_eq!(body["differ"], true);
+        assert_eq!(body["_filter"]["IncludeRule"][0], "/album");
+    }
+
+    #[tokio::test]
+    async fn report_is_read_from_the_job_output() {
+        let server = MockRcServer::start().await;
+        server.state().check_output = json!({
+            "success": false,
+            "status": "2 differences found",
+            "hashType": "md5",
+            "missingOnDst": ["album/new.mp3"],
+            "missingOnSrc": ["album/old.mp3"],
+            "differ": ["album/broken.mp3"],
+        })
+        .as_object()
+        .unwrap()
+        .clone();
+        let client = server.client();
+        let config = config();
+        let paths = config.build_filesystem_paths().unwrap();
+
+        let jobid = download::submit_sync_job(
+            &client,
+            &build_check_body(&config, &paths),
+            "/operations/check",
+        )
+        .await
+        .unwrap();
+        let result = check_result(&client, &CancelledJobs::default(), jobid)
+            .await
+            .unwrap();
+
+        assert_eq!(result.hash_type.as_deref(), Some("md5"));
+        assert_eq!(result.missing, ["album/new.mp3"]);
+        assert_eq!(result.extra, ["album/old.mp3"]);
+        assert_eq!(result.differing, ["album/broken.mp3"]);
+        assert!(result.errors.is_empty());
+
+        let redownloaded = redownload(&client, &paths, &result.differing)
+            .await
+            .unwrap();
+        assert_eq!(redownloaded, ["album/broken.mp3"]);
+        let state = server.state();
+        let (path, params) = state.calls.last().unwrap();
+        assert_eq!(path, "operations/copyfile");
+        assert_eq!(params["dstRemote"], "album/broken.mp3");
+        assert_eq!(params["_config"]["IgnoreTimes"], true);
+    }
+}
@@ -1,26 +1,53 @@
+use crate::error::AppError;
+use chrono::{DateTime, Utc};
 use std::path::Path;
 
+/// A file found below a local folder
+#[derive(Debug, Clone)]
+pub struct LocalFile {
+    /// Path relative to the scanned folder, separated by `/`
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
 #[tauri::command]
-pub fn scan_local_files(path: String) -> Result<Vec<String>, String> {
-    let root = Path::new(&path);
+pub fn scan_local_files(path: String) -> Result<Vec<String>, AppError> {
+    Ok(scan_local_tree(Path::new(&path))?
+        .into_iter()
+        .map(|file| file.path)
+        .collect())
+}
+
+/// List every file below a folder with its size and modification time
+pub fn scan_local_tree(root: &Path) -> Result<Vec<LocalFile>, AppError> {
     if !root.exists() || !root.is_dir() {
-        return Err("Destination is not a valid directory".to_string());
+        return Err(AppError::InvalidDestination(
+            "Destination is not a valid directory".to_string(),
+        ));
     }
 
     let mut files = Vec::new();
     let mut stack = vec![root.to_path_buf()];
 
     while let Some(dir) = stack.pop() {
-        let entries = std::fs::read_dir(&dir).map_err(|e| e.to_string())?;
+        let entries =
+            std::fs::read_dir(&dir).map_err(|e| AppError::io("Failed to read directory", e))?;
         for entry in entries {
-            let entry = entry.map_err(|e| e.to_string())?;
+            let entry = entry.map_err(|e| AppError::io("Failed to read directory", e))?;
             let path = entry.path();
             if path.is_dir() {
                 stack.push(path);
             } else {
                 if let Ok(relative) = path.strip_prefix(root) {
-                    let path_str = relative.to_string_lossy().replace('\\', "/");
-                    files.push(path_str);
+                    let metadata = entry.metadata().ok();
+                    files.push(LocalFile {
+                        path: relative.to_string_lossy().replace('\\', "/"),
+                        size: metadata.as_ref().map_or(0, |m| m.len()),
+                        modified: metadata
+                            .and_then(|m| m.modified().ok())
+                            .map(DateTime::<Utc>::from),
+                    });
                 }
             }
         }
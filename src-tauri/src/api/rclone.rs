@@ -1,12 +1,16 @@
-use tauri::AppHandle;
+use crate::error::AppError;
+use tauri::{AppHandle, Runtime};
 use tauri_plugin_shell::ShellExt;
 use tauri_plugin_shell::process::Command;
 
 pub mod logs;
+#[cfg(test)]
+pub(crate) mod mock;
 pub mod server;
 pub mod stats;
+pub mod supervisor;
 
-pub fn get_rclone_command(app: &AppHandle) -> Result<Command, String> {
+pub fn get_rclone_command<R: Runtime>(app: &AppHandle<R>) -> Result<Command, AppError> {
     #[cfg(target_os = "android")]
     {
         use std::fs;
@@ -14,13 +18,17 @@ pub fn get_rclone_command(app: &AppHandle) -> Result<Command, String> {
         let app_dir = "/data/data/com.inforno.unofficial_neuro_kar_manager/files";
         let path_file = format!("{}/native_lib_path.txt", app_dir);
 
-        let lib_dir = fs::read_to_string(&path_file)
-            .map_err(|e| format!("Failed to read native lib path from {}: {}", path_file, e))?;
+        let lib_dir = fs::read_to_string(&path_file).map_err(|e| {
+            AppError::io(
+                &format!("Failed to read native lib path from {}", path_file),
+                e,
+            )
+        })?;
 
         let app_data_dir = app
             .path()
             .app_local_data_dir()
-            .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to get app data dir: {}", e)))?;
         let config_path = app_data_dir.join("rclone.conf");
 
         Ok(app
@@ -33,15 +41,25 @@ pub fn get_rclone_command(app: &AppHandle) -> Result<Command, String> {
     }
     #[cfg(not(target_os = "android"))]
     {
-        app.shell().sidecar("rclone").map_err(|e| e.to_string())
+        app.shell()
+            .sidecar("rclone")
+            .map_err(|e| AppError::RcloneUnavailable(e.to_string()))
     }
 }
 
 // Command functions
+pub use logs::{__cmd__list_rclone_logs, __cmd__tail_rclone_log};
 pub use server::__cmd__stop_rc_server;
 pub use stats::__cmd__get_stats;
+pub use supervisor::__cmd__get_rclone_server_status;
 
 // Functions
-pub use logs::LogManager;
-pub use server::{get_sdk_client, is_server_running, stop_rc_server};
-pub use stats::get_stats;
+pub use logs::{
+    DryRunAction, LogChunk, LogLevel, LogManager, LogSession, RcloneLogEntry, SyncAction,
+    list_rclone_logs, tail_rclone_log,
+};
+pub use server::{get_sdk_client, is_server_running, is_session_client, stop_rc_server};
+pub use stats::{
+    JobMonitor, TransferProgress, TransferredFile, TransferringFile, get_stats, spawn_job_monitor,
+};
+pub use supervisor::{ServerState, ServerStatus, get_rclone_server_status, server_state};
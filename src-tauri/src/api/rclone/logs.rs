@@ -1,30 +1,147 @@
+use crate::error::AppError;
 use regex::Regex;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
-use tauri::{AppHandle, Manager};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
 use tokio::io::{AsyncBufReadExt, AsyncSeekExt};
 
+/// Number of previous server session logs to keep
+const MAX_LOG_SESSIONS: usize = 5;
+
+/// How often new log lines are emitted while a job runs
+const LOG_FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Maximum number of entries returned by a single tail request
+const DEFAULT_TAIL_LIMIT: usize = 500;
+
+static LOG_FOLLOWERS: AtomicUsize = AtomicUsize::new(0);
+static FOLLOWER_RUNNING: AtomicBool = AtomicBool::new(false);
+
 pub struct LogManager;
 
+/// A log file kept from a previous server session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSession {
+    pub name: String,
+    pub size: u64,
+}
+
+/// A page of log entries and the offset to continue from
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogChunk {
+    pub entries: Vec<RcloneLogEntry>,
+    pub next_offset: u64,
+}
+
 impl LogManager {
     /// Get the path to the rclone log file.
-    pub fn get_log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    pub fn get_log_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, AppError> {
         Ok(app
             .path()
             .app_local_data_dir()
-            .map_err(|e| format!("Failed to get app data dir: {}", e))?
+            .map_err(|e| AppError::Internal(format!("Failed to get app data dir: {}", e)))?
             .join("rclone.log"))
     }
 
-    /// Clear the log file (e.g., on server startup).
-    pub async fn clear(app: &AppHandle) -> Result<(), String> {
+    /// Get the directory holding logs of previous server sessions.
+    fn get_sessions_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, AppError> {
+        Ok(app
+            .path()
+            .app_local_data_dir()
+            .map_err(|e| AppError::Internal(format!("Failed to get app data dir: {}", e)))?
+            .join("logs"))
+    }
+
+    /// Move the current log file aside (e.g., on server startup), keeping
+    /// only the most recent sessions.
+    pub async fn rotate<R: Runtime>(app: &AppHandle<R>) -> Result<(), AppError> {
         let path = Self::get_log_path(app)?;
-        if path.exists() {
-            let _ = tokio::fs::remove_file(&path).await;
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let sessions_dir = Self::get_sessions_dir(app)?;
+        tokio::fs::create_dir_all(&sessions_dir)
+            .await
+            .map_err(|e| AppError::io("Failed to create log directory", e))?;
+
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S_%3f").to_string();
+        let rotated = Self::unused_session_path(&sessions_dir, &timestamp);
+        // Keep writing to the current log rather than losing it, e.g. while
+        // the previous server still holds it open on Windows
+        if tokio::fs::rename(&path, &rotated).await.is_err() {
+            return Ok(());
+        }
+
+        // Session names sort chronologically, drop the oldest ones
+        let mut sessions = Self::list_sessions(app).await?;
+        while sessions.len() > MAX_LOG_SESSIONS {
+            let oldest = sessions.remove(0);
+            let _ = tokio::fs::remove_file(sessions_dir.join(oldest.name)).await;
         }
         Ok(())
     }
 
+    /// A session log path for the given time that isn't taken yet. Sessions
+    /// started within the same millisecond get a sequence number, which
+    /// keeps the names sorting chronologically.
+    fn unused_session_path(sessions_dir: &Path, timestamp: &str) -> PathBuf {
+        let mut path = sessions_dir.join(format!("rclone-{}.log", timestamp));
+        let mut sequence = 1;
+        while path.exists() {
+            path = sessions_dir.join(format!("rclone-{}_{:03}.log", timestamp, sequence));
+            sequence += 1;
+        }
+        path
+    }
+
+    /// List the logs of previous server sessions, oldest first.
+    pub async fn list_sessions<R: Runtime>(
+        app: &AppHandle<R>,
+    ) -> Result<Vec<LogSession>, AppError> {
+        let sessions_dir = Self::get_sessions_dir(app)?;
+        if !sessions_dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut entries = tokio::fs::read_dir(&sessions_dir)
+            .await
+            .map_err(|e| AppError::io("Failed to read log directory", e))?;
+        let mut sessions = Vec::new();
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| AppError::io("Failed to read log directory", e))?
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with("rclone-") || !name.ends_with(".log") {
+                continue;
+            }
+            let size = entry.metadata().await.map(|m| m.len()).unwrap_or(0);
+            sessions.push(LogSession { name, size });
+        }
+
+        sessions.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(sessions)
+    }
+
+    /// Resolve a session log by name, or the current log when no name is given.
+    fn resolve_log_path(app: &AppHandle, session: Option<&str>) -> Result<PathBuf, AppError> {
+        match session {
+            None => Self::get_log_path(app),
+            Some(name) if name.contains(['/', '\\']) || name.contains("..") => Err(
+                AppError::InvalidRequest(format!("Invalid log session: {}", name)),
+            ),
+            Some(name) => Ok(Self::get_sessions_dir(app)?.join(name)),
+        }
+    }
+
     /// Get the current size of the log file to use as an offset.
     pub async fn get_current_offset(app: &AppHandle) -> u64 {
         match Self::get_log_path(app) {
@@ -36,56 +153,545 @@ impl LogManager {
         }
     }
 
-    /// Parse the log file from a given offset for deleted files.
-    pub async fn parse_deleted_files(
-        app: &AppHandle,
+    /// Read complete log entries after the given offset that match the filter,
+    /// stopping after `limit` matches. Returns the entries and the offset to
+    /// continue reading from. Lines that are not JSON log entries are skipped.
+    async fn read_entries(
+        log_path: &Path,
         start_offset: u64,
-    ) -> Result<Vec<String>, String> {
-        let log_path = Self::get_log_path(app)?;
+        limit: Option<usize>,
+        filter: impl Fn(&RcloneLogEntry) -> bool,
+    ) -> Result<(Vec<RcloneLogEntry>, u64), AppError> {
         if !log_path.exists() {
-            return Ok(vec![]);
+            return Ok((vec![], 0));
         }
 
         let mut file = tokio::fs::File::open(log_path)
             .await
-            .map_err(|e| format!("Failed to open log file: {}", e))?;
+            .map_err(|e| AppError::io("Failed to open log file", e))?;
+
+        // Start over if the log was rotated since the offset was taken
+        let len = file
+            .metadata()
+            .await
+            .map_err(|e| AppError::io("Failed to read log file", e))?
+            .len();
+        let mut offset = if start_offset > len { 0 } else { start_offset };
 
-        if start_offset > 0 {
-            file.seek(std::io::SeekFrom::Start(start_offset))
+        if offset > 0 {
+            file.seek(std::io::SeekFrom::Start(offset))
                 .await
-                .map_err(|e| format!("Failed to seek log file: {}", e))?;
+                .map_err(|e| AppError::io("Failed to seek log file", e))?;
         }
 
-        let reader = tokio::io::BufReader::new(file);
-        let mut lines = reader.lines();
-        let mut deleted_files = Vec::new();
+        let mut reader = tokio::io::BufReader::new(file);
+        let mut entries = Vec::new();
+        let mut line = String::new();
 
-        while let Some(line) = lines
-            .next_line()
-            .await
-            .map_err(|e| format!("Failed to read log line: {}", e))?
-        {
-            if line.contains("Skipped delete as --dry-run is set") {
-                if let Some(file_path) = Self::extract_deleted_file_path(&line) {
-                    deleted_files.push(file_path);
+        while limit.is_none_or(|limit| entries.len() < limit) {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .await
+                .map_err(|e| AppError::io("Failed to read log line", e))?;
+
+            // Leave partially written lines for the next read
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            offset += read as u64;
+
+            if let Some(entry) = RcloneLogEntry::parse(&line).filter(&filter) {
+                entries.push(entry);
+            }
+        }
+
+        Ok((entries, offset))
+    }
+
+    /// Read all log entries written after the given offset.
+    async fn read_entries_from(
+        app: &AppHandle,
+        start_offset: u64,
+    ) -> Result<Vec<RcloneLogEntry>, AppError> {
+        let log_path = Self::get_log_path(app)?;
+        let (entries, _) = Self::read_entries(&log_path, start_offset, None, |_| true).await?;
+        Ok(entries)
+    }
+
+    /// Emit new log entries as `rclone-log` events while at least one job is running.
+    pub fn start_following(app: &AppHandle) {
+        LOG_FOLLOWERS.fetch_add(1, Ordering::SeqCst);
+        if FOLLOWER_RUNNING.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let Ok(log_path) = Self::get_log_path(&app) else {
+                FOLLOWER_RUNNING.store(false, Ordering::SeqCst);
+                return;
+            };
+            let mut offset = Self::get_current_offset(&app).await;
+
+            loop {
+                tokio::time::sleep(LOG_FOLLOW_INTERVAL).await;
+
+                if let Ok((entries, next_offset)) =
+                    Self::read_entries(&log_path, offset, None, |_| true).await
+                {
+                    offset = next_offset;
+                    if !entries.is_empty() {
+                        let _ = app.emit("rclone-log", entries);
+                    }
+                }
+
+                if LOG_FOLLOWERS.load(Ordering::SeqCst) == 0 {
+                    FOLLOWER_RUNNING.store(false, Ordering::SeqCst);
+                    // Keep going if a job started while this follower was stopping
+                    if LOG_FOLLOWERS.load(Ordering::SeqCst) == 0
+                        || FOLLOWER_RUNNING.swap(true, Ordering::SeqCst)
+                    {
+                        break;
+                    }
                 }
             }
+        });
+    }
+
+    /// Stop following the log for a job started with `start_following`.
+    pub fn stop_following() {
+        let _ = LOG_FOLLOWERS.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+            count.checked_sub(1)
+        });
+    }
+
+    /// Parse the log file from a given offset for the actions a dry run skipped.
+    pub async fn parse_dry_run_actions(
+        app: &AppHandle,
+        start_offset: u64,
+    ) -> Result<Vec<DryRunAction>, AppError> {
+        let entries = Self::read_entries_from(app, start_offset).await?;
+        Ok(entries
+            .iter()
+            .filter_map(DryRunAction::from_entry)
+            .collect())
+    }
+
+    /// Parse the log file from a given offset for the changes a sync made.
+    pub async fn parse_sync_actions(
+        app: &AppHandle,
+        start_offset: u64,
+    ) -> Result<Vec<SyncAction>, AppError> {
+        let entries = Self::read_entries_from(app, start_offset).await?;
+        Ok(entries.iter().filter_map(SyncAction::from_entry).collect())
+    }
+
+    /// Parse the log file from a given offset for errors.
+    pub async fn parse_errors(
+        app: &AppHandle,
+        start_offset: u64,
+    ) -> Result<Vec<RcloneLogEntry>, AppError> {
+        let entries = Self::read_entries_from(app, start_offset).await?;
+        Ok(entries
+            .into_iter()
+            .filter(|entry| entry.level.is_error())
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+    #[serde(other)]
+    Unknown,
+}
+
+impl LogLevel {
+    pub fn is_error(self) -> bool {
+        matches!(
+            self,
+            Self::Error | Self::Critical | Self::Alert | Self::Emergency
+        )
+    }
+}
+
+/// A single line of rclone's `--use-json-log` output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RcloneLogEntry {
+    pub time: String,
+    pub level: LogLevel,
+    #[serde(default)]
+    pub object: Option<String>,
+    #[serde(rename = "msg")]
+    pub message: String,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl RcloneLogEntry {
+    /// Parse a JSON log line, returning `None` for anything else
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut entry: Self = serde_json::from_str(line.trim()).ok()?;
+
+        // Some rclone versions also prefix the message with the object name
+        if let Some(object) = &entry.object {
+            let prefix = format!("{}: ", object);
+            if entry.message.starts_with(&prefix) {
+                entry.message.drain(..prefix.len());
+            }
         }
 
-        Ok(deleted_files)
+        Some(entry)
     }
+}
 
-    fn extract_deleted_file_path(line: &str) -> Option<String> {
-        static RE: OnceLock<Regex> = OnceLock::new();
-        let re = RE.get_or_init(|| {
-            // Pattern: date time LEVEL: filename: message
-            // Capture the filename part between LEVEL: and the next colon
-            Regex::new(r"^\d{4}/\d{2}/\d{2} \d{2}:\d{2}:\d{2} (?:NOTICE|INFO|ERROR):\s+([^:]+):")
+impl std::fmt::Display for RcloneLogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.object {
+            Some(object) => write!(f, "{}: {}", object, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A change rclone reported it would make during a dry run
+#[derive(Debug, Clone, PartialEq)]
+pub enum DryRunAction {
+    Copy {
+        path: String,
+        size: Option<i64>,
+    },
+    Delete {
+        path: String,
+        size: Option<i64>,
+    },
+    /// A local file moved to a new name. Dry runs log the move against the
+    /// old name only, so the new one is known once rclone also logs the
+    /// rename.
+    Rename {
+        from: String,
+        to: Option<String>,
+        size: Option<i64>,
+    },
+}
+
+impl DryRunAction {
+    fn from_entry(entry: &RcloneLogEntry) -> Option<Self> {
+        static SKIPPED_RE: OnceLock<Regex> = OnceLock::new();
+        let skipped_re = SKIPPED_RE.get_or_init(|| {
+            // Pattern: Skipped action as --dry-run is set (size X)
+            Regex::new(r"^Skipped (copy|delete|move) as --dry-run is set(?: \(size ([^)]+)\))?")
                 .unwrap()
         });
 
-        re.captures(line)
-            .and_then(|caps| caps.get(1))
-            .map(|m| m.as_str().trim().to_string())
+        let object = entry.object.as_ref()?;
+
+        if let Some(caps) = skipped_re.captures(&entry.message) {
+            let path = object.clone();
+            let size = caps.get(2).and_then(|m| parse_size_suffix(m.as_str()));
+            return match &caps[1] {
+                "copy" => Some(Self::Copy { path, size }),
+                // Moves within the destination are renames of tracked files
+                "move" => Some(Self::Rename {
+                    from: path,
+                    to: None,
+                    size,
+                }),
+                _ => Some(Self::Delete { path, size }),
+            };
+        }
+
+        // Pattern: Renamed from "old filename", logged against the new filename
+        let from = entry
+            .message
+            .strip_prefix("Renamed from \"")?
+            .strip_suffix('"')?;
+        Some(Self::Rename {
+            from: from.to_string(),
+            to: Some(object.clone()),
+            size: None,
+        })
+    }
+}
+
+/// A change rclone reported making during a sync
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncAction {
+    Copied {
+        path: String,
+        replaced: bool,
+    },
+    Deleted {
+        path: String,
+    },
+    /// Moved within the destination or, when a backup folder is set, out of
+    /// the destination into the backup
+    Moved {
+        from: String,
+        to: Option<String>,
+    },
+    Renamed {
+        from: String,
+        to: String,
+    },
+    Failed {
+        path: Option<String>,
+        reason: String,
+    },
+}
+
+impl SyncAction {
+    fn from_entry(entry: &RcloneLogEntry) -> Option<Self> {
+        if entry.level.is_error() {
+            return Some(Self::Failed {
+                path: entry.object.clone(),
+                reason: entry.message.clone(),
+            });
+        }
+
+        let path = entry.object.clone()?;
+        let message = entry
+            .message
+            .strip_prefix("Multi-thread ")
+            .unwrap_or(&entry.message);
+
+        if let Some(kind) = message.strip_prefix("Copied (") {
+            return Some(Self::Copied {
+                path,
+                replaced: kind.starts_with("replaced"),
+            });
+        }
+        if message == "Deleted" {
+            return Some(Self::Deleted { path });
+        }
+        if let Some(moved) = message.strip_prefix("Moved (server-side)") {
+            let to = moved.strip_prefix(" to: ").map(str::to_string);
+            return Some(Self::Moved { from: path, to });
+        }
+
+        // Pattern: Renamed from "old filename", logged against the new filename
+        let from = message.strip_prefix("Renamed from \"")?.strip_suffix('"')?;
+        Some(Self::Renamed {
+            from: from.to_string(),
+            to: path,
+        })
+    }
+}
+
+/// Parse a size printed by rclone such as `512`, `1.500Mi` or `2Gi` into bytes
+fn parse_size_suffix(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    let value: f64 = number.parse().ok()?;
+
+    let multiplier: f64 = match suffix.trim_end_matches('B') {
+        "" => 1.0,
+        "Ki" => 1024.0,
+        "Mi" => 1024.0_f64.powi(2),
+        "Gi" => 1024.0_f64.powi(3),
+        "Ti" => 1024.0_f64.powi(4),
+        "Pi" => 1024.0_f64.powi(5),
+        "Ei" => 1024.0_f64.powi(6),
+        _ => return None,
+    };
+
+    Some((value * multiplier).round() as i64)
+}
+
+/// Read log entries from an offset, optionally filtered by level and a search
+/// term. Reads the current session unless a previous `session` is named.
+#[tauri::command]
+pub async fn tail_rclone_log(
+    app: AppHandle,
+    offset: u64,
+    levels: Option<Vec<LogLevel>>,
+    search: Option<String>,
+    limit: Option<usize>,
+    session: Option<String>,
+) -> Result<LogChunk, AppError> {
+    let log_path = LogManager::resolve_log_path(&app, session.as_deref())?;
+    let search = search
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty());
+
+    let filter = |entry: &RcloneLogEntry| {
+        let level_matches = levels
+            .as_ref()
+            .is_none_or(|levels| levels.contains(&entry.level));
+        let search_matches = search
+            .as_ref()
+            .is_none_or(|term| entry.to_string().to_lowercase().contains(term));
+        level_matches && search_matches
+    };
+
+    let limit = limit.unwrap_or(DEFAULT_TAIL_LIMIT);
+    let (entries, next_offset) =
+        LogManager::read_entries(&log_path, offset, Some(limit), filter).await?;
+
+    Ok(LogChunk {
+        entries,
+        next_offset,
+    })
+}
+
+/// List the logs kept from previous server sessions
+#[tauri::command]
+pub async fn list_rclone_logs(app: AppHandle) -> Result<Vec<LogSession>, AppError> {
+    LogManager::list_sessions(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRY_RUN_LOG: &str = r#"{"time":"2026-01-01T00:00:00Z","level":"notice","msg":"Skipped copy as --dry-run is set (size 1.5Mi)","object":"album/new.mp3","objectType":"*drive.Object","source":"operations/operations.go:2500"}
+{"time":"2026-01-01T00:00:00Z","level":"notice","msg":"album/old.mp3: Skipped delete as --dry-run is set (size 512)","object":"album/old.mp3","source":"operations/operations.go:2500"}
+{"time":"2026-01-01T00:00:01Z","level":"notice","msg":"album/moved.mp3: Skipped move as --dry-run is set (size 3.5Mi)","object":"album/moved.mp3","objectType":"*local.Object","source":"operations/operations.go:2500"}
+{"time":"2026-01-01T00:00:01Z","level":"notice","msg":"Renamed from \"album/before.mp3\"","object":"album/after.mp3"}
+{"time":"2026-01-01T00:00:01Z","level":"error","msg":"Failed to copy: quota exceeded","object":"album/big.mp3"}
+not a json line
+{"time":"2026-01-01T00:00:02Z","level":"info","msg":"There was nothing to transfer"}
+"#;
+
+    const SYNC_LOG: &str = r#"{"time":"2026-01-01T00:00:00Z","level":"info","msg":"Copied (new)","object":"album/new.mp3"}
+{"time":"2026-01-01T00:00:00Z","level":"info","msg":"Multi-thread Copied (replaced existing)","object":"album/big.flac"}
+{"time":"2026-01-01T00:00:01Z","level":"info","msg":"Moved (server-side) to: album/big.flac","object":"album/big.flac"}
+{"time":"2026-01-01T00:00:01Z","level":"info","msg":"Deleted","object":"album/gone.mp3"}
+{"time":"2026-01-01T00:00:01Z","level":"notice","msg":"Renamed from \"album/before.mp3\"","object":"album/after.mp3"}
+{"time":"2026-01-01T00:00:02Z","level":"error","msg":"Failed to copy: quota exceeded","object":"album/huge.mp3"}
+{"time":"2026-01-01T00:00:02Z","level":"info","msg":"Updated modification time in destination","object":"album/same.mp3"}
+"#;
+
+    #[tokio::test]
+    async fn sync_actions_are_parsed_from_json_log() {
+        let path = std::env::temp_dir().join(format!("rclone-sync-{}.log", std::process::id()));
+        tokio::fs::write(&path, SYNC_LOG).await.unwrap();
+
+        let (entries, _) = LogManager::read_entries(&path, 0, None, |_| true)
+            .await
+            .unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        let actions: Vec<SyncAction> = entries.iter().filter_map(SyncAction::from_entry).collect();
+        assert_eq!(
+            actions,
+            vec![
+                SyncAction::Copied {
+                    path: "album/new.mp3".to_string(),
+                    replaced: false,
+                },
+                SyncAction::Copied {
+                    path: "album/big.flac".to_string(),
+                    replaced: true,
+                },
+                SyncAction::Moved {
+                    from: "album/big.flac".to_string(),
+                    to: Some("album/big.flac".to_string()),
+                },
+                SyncAction::Deleted {
+                    path: "album/gone.mp3".to_string(),
+                },
+                SyncAction::Renamed {
+                    from: "album/before.mp3".to_string(),
+                    to: "album/after.mp3".to_string(),
+                },
+                SyncAction::Failed {
+                    path: Some("album/huge.mp3".to_string()),
+                    reason: "Failed to copy: quota exceeded".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sessions_of_the_same_millisecond_keep_their_order() {
+        let dir = std::env::temp_dir().join(format!("rclone-sessions-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut names = Vec::new();
+        for _ in 0..3 {
+            let path = LogManager::unused_session_path(&dir, "20260101_000000_123");
+            std::fs::write(&path, "").unwrap();
+            names.push(path.file_name().unwrap().to_string_lossy().to_string());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            names,
+            [
+                "rclone-20260101_000000_123.log",
+                "rclone-20260101_000000_123_001.log",
+                "rclone-20260101_000000_123_002.log",
+            ]
+        );
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(sorted, names);
+    }
+
+    #[tokio::test]
+    async fn dry_run_actions_are_parsed_from_json_log() {
+        let path = std::env::temp_dir().join(format!("rclone-dry-run-{}.log", std::process::id()));
+        tokio::fs::write(&path, DRY_RUN_LOG).await.unwrap();
+
+        let (entries, next_offset) = LogManager::read_entries(&path, 0, None, |_| true)
+            .await
+            .unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(next_offset, DRY_RUN_LOG.len() as u64);
+        assert_eq!(entries.len(), 6);
+        assert_eq!(
+            entries[1].message,
+            "Skipped delete as --dry-run is set (size 512)"
+        );
+
+        let actions: Vec<DryRunAction> = entries
+            .iter()
+            .filter_map(DryRunAction::from_entry)
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                DryRunAction::Copy {
+                    path: "album/new.mp3".to_string(),
+                    size: Some(1_572_864),
+                },
+                DryRunAction::Delete {
+                    path: "album/old.mp3".to_string(),
+                    size: Some(512),
+                },
+                DryRunAction::Rename {
+                    from: "album/moved.mp3".to_string(),
+                    to: None,
+                    size: Some(3_670_016),
+                },
+                DryRunAction::Rename {
+                    from: "album/before.mp3".to_string(),
+                    to: Some("album/after.mp3".to_string()),
+                    size: None,
+                },
+            ]
+        );
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|entry| entry.level.is_error())
+                .count(),
+            1
+        );
     }
 }
@@ -0,0 +1,396 @@
+//! In-process stand-in for `rclone rcd`, so code that talks to the RC API can
+//! be tested offline. Only the calls the app makes are implemented.
+
+use super::server::RcEndpoint;
+use base64::Engine;
+use rclone_sdk::Client;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Job ids are unique across servers, so an id never names jobs of two
+/// tests running in parallel
+static NEXT_JOB_ID: AtomicI64 = AtomicI64::new(1);
+
+/// A job started through `sync/sync`, `sync/copy` or `operations/check`
+#[derive(Debug, Clone)]
+pub struct MockJob {
+    pub path: String,
+    pub params: Map<String, Value>,
+    /// Status polls left before the job reports it finished
+    pub polls_left: u32,
+    pub finished: bool,
+    pub error: String,
+    /// Output reported by `job/status` once finished
+    pub output: Value,
+}
+
+#[derive(Debug, Default)]
+pub struct MockState {
+    pub pid: i64,
+    pub jobs: HashMap<i64, MockJob>,
+    /// Status polls a new job stays unfinished for
+    pub job_polls: u32,
+    /// Error new jobs finish with, empty for success
+    pub job_error: String,
+    /// Output of finished `operations/check` jobs
+    pub check_output: Map<String, Value>,
+    /// Fields returned by `core/stats` on top of zeroed defaults
+    pub stats: Map<String, Value>,
+    /// Remotes returned by `config/dump`, keyed by name
+    pub remotes: Map<String, Value>,
+    /// Entries returned by `operations/list`
+    pub files: Vec<Value>,
+    /// Error returned by `operations/list` instead of the entries
+    pub list_error: Option<String>,
+    /// Entries returned by `core/transferred`, keyed by stats group
+    pub transferred: HashMap<String, Vec<Value>>,
+    /// Errors `operations/copyfile` returns, keyed by source remote
+    pub copy_errors: HashMap<String, String>,
+    /// Rate set through `core/bwlimit`, `off` when unset
+    pub bw_limit: Option<String>,
+    /// Every call received, as path and merged query and body parameters
+    pub calls: Vec<(String, Map<String, Value>)>,
+}
+
+pub struct MockRcServer {
+    endpoint: RcEndpoint,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockRcServer {
+    /// Start a server on a free local port. It runs until the test's
+    /// runtime shuts down.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = RcEndpoint {
+            port: listener.local_addr().unwrap().port(),
+            user: "mock".to_string(),
+            pass: "secret".to_string(),
+        };
+        let state = Arc::new(Mutex::new(MockState {
+            pid: 4242,
+            ..Default::default()
+        }));
+
+        let credentials = format!("{}:{}", endpoint.user, endpoint.pass);
+        let auth = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        );
+        let shared = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = shared.clone();
+                let auth = auth.clone();
+                tokio::spawn(async move {
+                    let _ = serve(stream, &state, &auth).await;
+                });
+            }
+        });
+
+        Self { endpoint, state }
+    }
+
+    pub fn endpoint(&self) -> RcEndpoint {
+        self.endpoint.clone()
+    }
+
+    /// A client authenticated the same way the app's clients are
+    pub fn client(&self) -> Client {
+        self.endpoint.client()
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Paths of the calls received so far
+    pub fn call_paths(&self) -> Vec<String> {
+        self.state()
+            .calls
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+}
+
+/// Handle a single request on a connection, then close it
+async fn serve(stream: TcpStream, state: &Mutex<MockState>, auth: &str) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let target = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    let (status, content_type, response) =
+        if headers.get("authorization").map(String::as_str) != Some(auth) {
+            (401, "text/plain", "Unauthorized".to_string())
+        } else {
+            let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+            let mut params = parse_query(query);
+            if let Ok(Value::Object(fields)) = serde_json::from_slice(&body) {
+                params.extend(fields);
+            }
+            let (status, value) = handle(
+                &mut state.lock().unwrap(),
+                path.trim_start_matches('/'),
+                params,
+            );
+            (status, "application/json", value.to_string())
+        };
+
+    let head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        response.len()
+    );
+    let mut stream = reader.into_inner();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn handle(state: &mut MockState, path: &str, params: Map<String, Value>) -> (u16, Value) {
+    state.calls.push((path.to_string(), params.clone()));
+
+    match path {
+        "core/pid" => (200, json!({ "pid": state.pid })),
+        "core/stats" => {
+            let mut stats = default_stats();
+            stats.extend(state.stats.clone());
+            (200, Value::Object(stats))
+        }
+        "sync/sync" | "sync/copy" | "operations/check" => {
+            let id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+            let output = if path == "operations/check" {
+                Value::Object(state.check_output.clone())
+            } else {
+                json!({})
+            };
+            state.jobs.insert(
+                id,
+                MockJob {
+                    path: path.to_string(),
+                    params,
+                    polls_left: state.job_polls,
+                    finished: false,
+                    error: String::new(),
+                    output,
+                },
+            );
+            (200, json!({ "jobid": id }))
+        }
+        "job/status" => {
+            let job_error = state.job_error.clone();
+            let Some((id, job)) = find_job(state, &params) else {
+                return rc_error(path, params, "job not found");
+            };
+            if !job.finished {
+                if job.polls_left == 0 {
+                    job.finished = true;
+                    job.error = job_error;
+                } else {
+                    job.polls_left -= 1;
+                }
+            }
+            (
+                200,
+                json!({
+                    "id": id,
+                    "finished": job.finished,
+                    "success": job.finished && job.error.is_empty(),
+                    "error": job.error,
+                    "duration": 0.0,
+                    "startTime": "",
+                    "endTime": "",
+                    "output": if job.finished { job.output.clone() } else { json!({}) },
+                }),
+            )
+        }
+        "job/stop" => {
+            let Some((_, job)) = find_job(state, &params) else {
+                return rc_error(path, params, "job not found");
+            };
+            job.finished = true;
+            job.error = "context canceled".to_string();
+            (200, json!({}))
+        }
+        "operations/list" => match &state.list_error {
+            Some(error) => {
+                let error = error.clone();
+                rc_error(path, params, &error)
+            }
+            None => (200, json!({ "list": state.files })),
+        },
+        "operations/copyfile" => {
+            let src_remote = param_str(&params, "srcRemote").unwrap_or_default();
+            match state.copy_errors.get(&src_remote).cloned() {
+                Some(error) => rc_error(path, params, &error),
+                None => (200, json!({})),
+            }
+        }
+        "core/transferred" => {
+            let group = param_str(&params, "group").unwrap_or_default();
+            let transferred = state.transferred.get(&group).cloned().unwrap_or_default();
+            (200, json!({ "transferred": transferred }))
+        }
+        "core/bwlimit" => {
+            if let Some(rate) = param_str(&params, "rate") {
+                state.bw_limit = Some(rate);
+            }
+            let rate = state.bw_limit.clone().unwrap_or_else(|| "off".to_string());
+            (
+                200,
+                json!({
+                    "rate": rate,
+                    "bytesPerSecond": -1,
+                    "bytesPerSecondRx": -1,
+                    "bytesPerSecondTx": -1,
+                }),
+            )
+        }
+        "config/dump" => (200, Value::Object(state.remotes.clone())),
+        "config/create" => {
+            let name = param_str(&params, "name");
+            let mut remote = param_str(&params, "parameters")
+                .and_then(|parameters| serde_json::from_str::<Map<String, Value>>(&parameters).ok())
+                .unwrap_or_default();
+            remote.insert(
+                "type".to_string(),
+                json!(param_str(&params, "type").unwrap_or_default()),
+            );
+            match name {
+                Some(name) => {
+                    state.remotes.insert(name, Value::Object(remote));
+                    (200, json!({}))
+                }
+                None => rc_error(path, params, "name is required"),
+            }
+        }
+        _ => {
+            let (_, body) = rc_error(path, params, "couldn't find method");
+            (404, body)
+        }
+    }
+}
+
+/// The job named by a `jobid` parameter, sent as a number or a query string
+fn find_job<'a>(
+    state: &'a mut MockState,
+    params: &Map<String, Value>,
+) -> Option<(i64, &'a mut MockJob)> {
+    let id = match params.get("jobid")? {
+        Value::Number(number) => number.as_f64()?,
+        Value::String(text) => text.parse().ok()?,
+        _ => return None,
+    } as i64;
+    state.jobs.get_mut(&id).map(|job| (id, job))
+}
+
+fn param_str(params: &Map<String, Value>, key: &str) -> Option<String> {
+    params.get(key)?.as_str().map(str::to_string)
+}
+
+/// An error body shaped like rclone's
+fn rc_error(path: &str, params: Map<String, Value>, message: &str) -> (u16, Value) {
+    (
+        500,
+        json!({
+            "error": message,
+            "input": params,
+            "path": path,
+            "status": 500,
+        }),
+    )
+}
+
+fn default_stats() -> Map<String, Value> {
+    let fields = [
+        "bytes",
+        "checks",
+        "deletedDirs",
+        "deletes",
+        "elapsedTime",
+        "errors",
+        "renames",
+        "serverSideCopies",
+        "serverSideCopyBytes",
+        "serverSideMoveBytes",
+        "serverSideMoves",
+        "speed",
+        "totalBytes",
+        "totalChecks",
+        "totalTransfers",
+        "transferTime",
+        "transfers",
+    ];
+    let mut stats: Map<String, Value> = fields
+        .iter()
+        .map(|field| (field.to_string(), json!(0)))
+        .collect();
+    stats.insert("fatalError".to_string(), json!(false));
+    stats.insert("retryError".to_string(), json!(false));
+    stats
+}
+
+fn parse_query(query: &str) -> Map<String, Value> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), Value::String(percent_decode(value)))
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'+', _) => decoded.push(b' '),
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 2;
+            }
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -1,63 +1,197 @@
 use super::LogManager;
-use rclone_sdk::Client;
-use std::sync::LazyLock;
+use super::supervisor::{self, ServerState};
+use crate::error::AppError;
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use rclone_sdk::{Client, ClientInfo};
+use std::sync::{LazyLock, RwLock};
 use std::time::Duration;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, Runtime};
 use tokio::sync::Mutex;
 
-pub const RC_PORT: u16 = 5572;
-pub const RC_URL: &str = "http://localhost:5572";
-
 static SHUTDOWN_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+static STARTUP_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
 
-/// Helper to check if the RC server is listening
-pub async fn is_server_running() -> bool {
-    let client = Client::new(RC_URL);
+/// The RC server started by this app
+static SESSION: LazyLock<RwLock<Option<RcSession>>> = LazyLock::new(|| RwLock::new(None));
+
+/// How many ports to try before giving up on a foreign process holding them
+const MAX_START_ATTEMPTS: usize = 3;
+
+const FOREIGN_SERVER_ERROR: &str = "Another process is answering on the rclone RC port";
+
+/// Address and credentials of an RC server
+#[derive(Clone)]
+pub struct RcEndpoint {
+    pub port: u16,
+    pub user: String,
+    pub pass: String,
+}
+
+impl RcEndpoint {
+    /// Pick a free local port and random credentials for a new server
+    fn generate() -> Result<Self, AppError> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")
+            .map_err(|e| AppError::io("Failed to find a free port", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| AppError::io("Failed to find a free port", e))?
+            .port();
+
+        Ok(Self {
+            port,
+            user: random_token(16),
+            pass: random_token(32),
+        })
+    }
+
+    /// Build an SDK client for this endpoint. The credentials in the URL are
+    /// sent as basic auth on every request, including raw ones built from
+    /// the client's base URL.
+    pub fn client(&self) -> Client {
+        Client::new(&format!(
+            "http://{}:{}@127.0.0.1:{}",
+            self.user, self.pass, self.port
+        ))
+    }
+}
+
+#[derive(Clone)]
+struct RcSession {
+    endpoint: RcEndpoint,
+    pid: u32,
+    child: crate::SharedChild,
+}
+
+/// Who answered on the RC endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerIdentity {
+    /// The rclone process this app spawned
+    Ours,
+    /// Some other process, e.g. a user's own `rclone rcd`
+    Foreign,
+    /// Nothing is listening
+    Unreachable,
+}
+
+fn random_token(len: usize) -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+fn current_session() -> Option<RcSession> {
+    SESSION.read().ok().and_then(|session| session.clone())
+}
+
+/// Pid of the server process started for the current session
+pub(super) fn session_pid() -> Option<u32> {
+    current_session().map(|session| session.pid)
+}
+
+fn current_endpoint() -> Option<RcEndpoint> {
+    current_session().map(|session| session.endpoint)
+}
+
+/// Whether a client was built for the current server session, as opposed to
+/// one that crashed or was replaced since
+pub fn is_session_client(client: &Client) -> bool {
+    current_endpoint().is_some_and(|endpoint| endpoint.client().baseurl() == client.baseurl())
+}
+
+/// Kill the server process of the current session and forget it
+pub fn discard_session() {
+    let session = SESSION.write().ok().and_then(|mut session| session.take());
+    if let Some(session) = session {
+        session.child.kill();
+    }
+}
+
+/// Check that the process answering on our endpoint is the one we spawned
+pub async fn check_server_identity() -> ServerIdentity {
+    let Some(session) = current_session() else {
+        return ServerIdentity::Unreachable;
+    };
+    identify_server(&session.endpoint.client(), session.pid).await
+}
+
+/// Check whether the server a client talks to runs with the given pid
+async fn identify_server(client: &Client, pid: u32) -> ServerIdentity {
     // core/pid is a lightweight check
-    client.core_pid(None, None).await.is_ok()
+    match client.core_pid(None, None).await {
+        Ok(response) if response.pid == pid as i64 => ServerIdentity::Ours,
+        Ok(_) => ServerIdentity::Foreign,
+        Err(rclone_sdk::Error::CommunicationError(_)) => ServerIdentity::Unreachable,
+        // Something answered but rejected our credentials
+        Err(_) => ServerIdentity::Foreign,
+    }
+}
+
+/// Helper to check if our RC server is listening
+pub async fn is_server_running() -> bool {
+    check_server_identity().await == ServerIdentity::Ours
 }
 
 /// Starts the rclone RC server in the background
-pub async fn start_rc_server(app: &AppHandle) -> Result<(), String> {
-    // Clear log file on startup
-    LogManager::clear(app).await?;
+pub async fn start_rc_server<R: Runtime>(app: &AppHandle<R>) -> Result<(), AppError> {
+    // Keep the previous session's log and start a fresh one
+    LogManager::rotate(app).await?;
     let log_file = LogManager::get_log_path(app)?;
 
+    let endpoint = RcEndpoint::generate()?;
     let sidecar_command = super::get_rclone_command(app)?;
 
-    let (mut _rx, child) = sidecar_command
+    // Credentials go through the environment so they don't show up in the process list
+    let (rx, child) = sidecar_command
+        .env("RCLONE_RC_USER", &endpoint.user)
+        .env("RCLONE_RC_PASS", &endpoint.pass)
         .args(&[
             "rcd",
-            "--rc-no-auth",
-            &format!("--rc-addr=localhost:{}", RC_PORT),
+            &format!("--rc-addr=127.0.0.1:{}", endpoint.port),
             "--log-file",
             &log_file.to_string_lossy().to_string(),
             "--log-level",
             "INFO",
+            "--use-json-log",
         ])
         .spawn()
-        .map_err(|e| format!("Failed to spawn rclone rcd: {}", e))?;
+        .map_err(|e| AppError::RcloneUnavailable(format!("Failed to spawn rclone rcd: {}", e)))?;
 
+    let pid = child.pid();
     let manager = app.state::<crate::SidecarManager>();
-    manager.add(child);
+    let child = manager.add(child);
+
+    if let Ok(mut session) = SESSION.write() {
+        *session = Some(RcSession {
+            endpoint,
+            pid,
+            child,
+        });
+    }
+
+    supervisor::spawn_supervisor(app, rx, pid);
 
     Ok(())
 }
 
-/// Waits for the RC server to become available
-pub async fn wait_for_server() -> Result<(), String> {
+/// Waits for the RC server to become available and returns who answered,
+/// or `Unreachable` if nothing did in time
+pub async fn wait_for_server() -> ServerIdentity {
     for _ in 0..20 {
         // 10 seconds total
-        if is_server_running().await {
-            return Ok(());
+        match check_server_identity().await {
+            ServerIdentity::Unreachable => {}
+            identity => return identity,
         }
         tokio::time::sleep(Duration::from_millis(500)).await;
     }
-    Err("Timed out waiting for rclone rc server".to_string())
+    ServerIdentity::Unreachable
 }
 
 /// Waits for the RC server to stop
-pub async fn wait_for_server_shutdown() -> Result<(), String> {
+pub async fn wait_for_server_shutdown() -> Result<(), AppError> {
     for _ in 0..20 {
         // 10 seconds total
         if !is_server_running().await {
@@ -65,31 +199,139 @@ pub async fn wait_for_server_shutdown() -> Result<(), String> {
         }
         tokio::time::sleep(Duration::from_millis(500)).await;
     }
-    Err("Timed out waiting for rclone rc server to stop".to_string())
+    Err(AppError::Internal(
+        "Timed out waiting for rclone rc server to stop".to_string(),
+    ))
 }
 
 /// Returns an authenticated SDK Client, ensuring the server is running.
-pub async fn get_sdk_client(app: &AppHandle) -> Result<Client, String> {
+/// An app managing an `RcEndpoint`, like one built by a test around a mock
+/// server, talks to that server instead of starting its own.
+pub async fn get_sdk_client<R: Runtime>(app: &AppHandle<R>) -> Result<Client, AppError> {
+    if let Some(endpoint) = app.try_state::<RcEndpoint>() {
+        return Ok(endpoint.client());
+    }
+
+    // Lock so concurrent callers don't each start a server
+    let _guard = STARTUP_LOCK.lock().await;
+
     if !is_server_running().await {
-        start_rc_server(app).await?;
-        wait_for_server().await?;
+        discard_session();
+
+        // Never attach to a stranger's server, try another port instead
+        for attempt in 1..=MAX_START_ATTEMPTS {
+            start_rc_server(app).await?;
+            match wait_for_server().await {
+                ServerIdentity::Ours => break,
+                ServerIdentity::Foreign if attempt < MAX_START_ATTEMPTS => discard_session(),
+                ServerIdentity::Foreign => {
+                    discard_session();
+                    return Err(AppError::RcloneUnavailable(
+                        FOREIGN_SERVER_ERROR.to_string(),
+                    ));
+                }
+                ServerIdentity::Unreachable => {
+                    discard_session();
+                    return Err(AppError::RcloneUnavailable(
+                        "Timed out waiting for rclone rc server".to_string(),
+                    ));
+                }
+            }
+        }
+
+        supervisor::set_state(app, ServerState::Running);
     }
-    Ok(Client::new(RC_URL))
+
+    current_endpoint()
+        .map(|endpoint| endpoint.client())
+        .ok_or(AppError::RcloneUnavailable(
+            "rclone rc server is not running".to_string(),
+        ))
 }
 
 #[tauri::command]
-pub async fn stop_rc_server() -> Result<(), String> {
+pub async fn stop_rc_server(app: AppHandle) -> Result<(), AppError> {
     // Lock to prevent concurrent shutdowns
     let _guard = SHUTDOWN_LOCK.lock().await;
 
+    let Some(endpoint) = current_endpoint() else {
+        return Ok(());
+    };
+
     if is_server_running().await {
-        let client = Client::new(RC_URL);
-        client
-            .core_quit(None, None, None)
-            .await
-            .map_err(|e| format!("Failed to stop rclone: {}", e))?;
+        // Mark the stop as intended before the process exits
+        supervisor::set_state(&app, ServerState::Stopped);
+
+        let client = endpoint.client();
+        client.core_quit(None, None, None).await?;
 
         wait_for_server_shutdown().await?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rclone::mock::MockRcServer;
+
+    #[tokio::test]
+    async fn commands_use_a_managed_endpoint() {
+        let server = MockRcServer::start().await;
+        server
+            .state()
+            .stats
+            .insert("bytes".to_string(), serde_json::json!(1234));
+        let app = tauri::test::mock_app();
+        app.manage(server.endpoint());
+
+        let stats = crate::api::rclone::get_stats(app.handle().clone())
+            .await
+            .unwrap();
+
+        assert_eq!(stats.bytes, 1234.0);
+        // The mock answered without a server process being started
+        assert_eq!(server.call_paths(), ["core/stats"]);
+    }
+
+    #[tokio::test]
+    async fn server_with_our_pid_is_ours() {
+        let server = MockRcServer::start().await;
+
+        let identity = identify_server(&server.client(), 4242).await;
+
+        assert_eq!(identity, ServerIdentity::Ours);
+    }
+
+    #[tokio::test]
+    async fn server_with_another_pid_is_foreign() {
+        let server = MockRcServer::start().await;
+
+        let identity = identify_server(&server.client(), 1).await;
+
+        assert_eq!(identity, ServerIdentity::Foreign);
+    }
+
+    #[tokio::test]
+    async fn server_rejecting_our_credentials_is_foreign() {
+        let server = MockRcServer::start().await;
+        let endpoint = RcEndpoint {
+            pass: "wrong".to_string(),
+            ..server.endpoint()
+        };
+
+        let identity = identify_server(&endpoint.client(), 4242).await;
+
+        assert_eq!(identity, ServerIdentity::Foreign);
+        assert!(server.call_paths().is_empty());
+    }
+
+    #[tokio::test]
+    async fn closed_port_is_unreachable() {
+        let endpoint = RcEndpoint::generate().unwrap();
+
+        let identity = identify_server(&endpoint.client(), 4242).await;
+
+        assert_eq!(identity, ServerIdentity::Unreachable);
+    }
+}
@@ -1,13 +1,220 @@
+use super::LogManager;
 use super::server::get_sdk_client;
-use tauri::AppHandle;
+use super::supervisor::{ServerState, server_state};
+use crate::error::AppError;
+use rclone_sdk::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// How often a job monitor reports progress
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A file currently being transferred by a job
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferringFile {
+    pub name: String,
+    pub size: i64,
+    pub bytes: i64,
+    pub percentage: f64,
+    pub speed: f64,
+    pub eta: Option<f64>,
+}
+
+/// Progress of a single rclone job, emitted as `transfer-progress`
+/// while it runs and as `transfer-finished` once it ends
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferProgress {
+    pub jobid: i64,
+    pub bytes: i64,
+    pub total_bytes: i64,
+    pub speed: f64,
+    pub eta: Option<f64>,
+    pub elapsed_time: f64,
+    pub transfers: i64,
+    pub total_transfers: i64,
+    pub checks: i64,
+    pub total_checks: i64,
+    pub deletes: i64,
+    pub renames: i64,
+    pub errors: i64,
+    pub last_error: Option<String>,
+    pub transferring: Vec<TransferringFile>,
+}
+
+impl TransferProgress {
+    fn from_stats(jobid: i64, stats: rclone_sdk::types::CoreStatsResponse) -> Self {
+        Self {
+            jobid,
+            bytes: stats.bytes as i64,
+            total_bytes: stats.total_bytes as i64,
+            speed: stats.speed,
+            eta: stats.eta,
+            elapsed_time: stats.elapsed_time,
+            transfers: stats.transfers as i64,
+            total_transfers: stats.total_transfers as i64,
+            checks: stats.checks as i64,
+            total_checks: stats.total_checks as i64,
+            deletes: stats.deletes as i64,
+            renames: stats.renames as i64,
+            errors: stats.errors as i64,
+            last_error: stats.last_error,
+            transferring: stats
+                .transferring
+                .into_iter()
+                .map(|t| TransferringFile {
+                    name: t.name.unwrap_or_default(),
+                    size: t.size.unwrap_or(0.0) as i64,
+                    bytes: t.bytes.unwrap_or(0.0) as i64,
+                    percentage: t.percentage.unwrap_or(0.0),
+                    speed: t.speed.unwrap_or(0.0),
+                    eta: t.eta,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A file a job is done with, as listed by `core/transferred`. Besides
+/// copies this covers deletions, moves and renames, told apart by `what`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferredFile {
+    pub name: String,
+    /// What rclone did with the file, like `transferring` or `deleting`
+    pub what: String,
+    pub dst_fs: Option<String>,
+    pub src_remote: Option<String>,
+    pub dst_remote: Option<String>,
+    pub error: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+impl TransferredFile {
+    fn from_item(item: rclone_sdk::types::CoreTransferredResponseTransferredItem) -> Self {
+        Self {
+            name: item.name.unwrap_or_default(),
+            what: item
+                .what
+                .map_or_else(|| "transferring".to_string(), |what| what.to_string()),
+            dst_fs: item.dst_fs,
+            src_remote: item.src_remote,
+            dst_remote: item.dst_remote,
+            error: item.error.filter(|error| !error.is_empty()),
+            completed_at: item.completed_at,
+        }
+    }
+
+    /// Whether the file was copied to the destination without errors
+    pub fn is_copied(&self) -> bool {
+        self.what == "transferring" && self.error.is_none()
+    }
+}
+
+/// The monitor of a running job, which hands over the files the job
+/// transferred once it ends
+pub struct JobMonitor(tauri::async_runtime::JoinHandle<Vec<TransferredFile>>);
+
+impl JobMonitor {
+    /// Wait for the job to end and return the files it was done with, in
+    /// the order they completed
+    pub async fn transferred(self) -> Vec<TransferredFile> {
+        self.0.await.unwrap_or_default()
+    }
+}
+
+/// Fetch the stats of a single job's stats group
+async fn get_job_progress(client: &Client, jobid: i64) -> Result<TransferProgress, AppError> {
+    let group = format!("job/{}", jobid);
+    let response = client.core_stats(None, None, Some(&group), None).await?;
+
+    Ok(TransferProgress::from_stats(jobid, response.into_inner()))
+}
+
+/// Add the files a job completed since the last call. rclone only keeps
+/// the most recent ones, so this has to be called while the job runs.
+async fn collect_transferred(
+    client: &Client,
+    jobid: i64,
+    seen: &mut HashSet<TransferredFile>,
+    transferred: &mut Vec<TransferredFile>,
+) {
+    let group = format!("job/{}", jobid);
+    let Ok(response) = client.core_transferred(None, None, Some(&group)).await else {
+        return;
+    };
+    for item in response.into_inner().transferred {
+        let file = TransferredFile::from_item(item);
+        if seen.insert(file.clone()) {
+            transferred.push(file);
+        }
+    }
+}
+
+/// Whether a failed status request means the job won't report anymore,
+/// rather than a hiccup the next request can get past. A crashed server
+/// is restarted without its jobs.
+fn is_job_gone(error: AppError) -> bool {
+    matches!(error, AppError::NotFound(_))
+        || matches!(
+            server_state(),
+            ServerState::Stopped | ServerState::Crashed | ServerState::Failed
+        )
+}
+
+/// Spawn a task that reports the progress of a job until it finishes,
+/// collecting the files it transferred along the way
+pub fn spawn_job_monitor(app: &AppHandle, client: &Client, jobid: i64) -> JobMonitor {
+    let app = app.clone();
+    let client = client.clone();
+
+    LogManager::start_following(&app);
+    JobMonitor(tauri::async_runtime::spawn(async move {
+        let mut seen = HashSet::new();
+        let mut transferred = Vec::new();
+        loop {
+            tokio::time::sleep(PROGRESS_INTERVAL).await;
+
+            let finished = match client.job_status(None, jobid as f64).await {
+                Ok(status) => status.into_inner().finished,
+                Err(e) => is_job_gone(AppError::from(e)),
+            };
+            collect_transferred(&client, jobid, &mut seen, &mut transferred).await;
+
+            match get_job_progress(&client, jobid).await {
+                Ok(progress) if finished => {
+                    let _ = app.emit("transfer-finished", progress);
+                    break;
+                }
+                Ok(progress) => {
+                    let _ = app.emit("transfer-progress", progress);
+                }
+                // Still report the end, so the job isn't shown as running
+                Err(_) if finished => {
+                    let progress = TransferProgress {
+                        jobid,
+                        ..Default::default()
+                    };
+                    let _ = app.emit("transfer-finished", progress);
+                    break;
+                }
+                Err(_) => {}
+            }
+        }
+        LogManager::stop_following();
+        transferred
+    }))
+}
 
 #[tauri::command]
-pub async fn get_stats(app: AppHandle) -> Result<rclone_sdk::types::CoreStatsResponse, String> {
+pub async fn get_stats<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<rclone_sdk::types::CoreStatsResponse, AppError> {
     let client = get_sdk_client(&app).await?;
-    let response = client
-        .core_stats(None, None, None, None)
-        .await
-        .map_err(|e| format!("Failed to fetch stats: {}", e))?;
+    let response = client.core_stats(None, None, None, None).await?;
 
     Ok(response.into_inner())
 }
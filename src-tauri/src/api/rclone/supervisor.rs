@@ -0,0 +1,163 @@
+use super::server;
+use crate::error::AppError;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+use tauri::async_runtime::Receiver;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_shell::process::CommandEvent;
+
+/// Number of stderr lines kept from the server process
+const MAX_STDERR_LINES: usize = 20;
+
+/// How many times a crashed server is restarted before giving up
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+
+/// Delay before the first restart, doubled after every failed attempt
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+static STATUS: LazyLock<RwLock<ServerStatus>> =
+    LazyLock::new(|| RwLock::new(ServerStatus::default()));
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ServerState {
+    /// Not started yet, or stopped on request
+    #[default]
+    Stopped,
+    Running,
+    /// Exited unexpectedly, a restart will follow
+    Crashed,
+    Restarting,
+    /// Could not be restarted
+    Failed,
+}
+
+/// State of the RC server process, emitted as `rclone-server-status`
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub state: ServerState,
+    /// Exit code of the last server process that exited
+    pub exit_code: Option<i32>,
+    /// Signal that terminated the last server process, if any
+    pub signal: Option<i32>,
+    /// Last lines the server wrote to stderr before exiting
+    pub stderr: Vec<String>,
+    pub restart_attempt: Option<u32>,
+}
+
+/// Current state of the RC server
+pub fn server_state() -> ServerState {
+    STATUS
+        .read()
+        .map(|status| status.state)
+        .unwrap_or(ServerState::Stopped)
+}
+
+fn update_status<R: Runtime>(app: &AppHandle<R>, update: impl FnOnce(&mut ServerStatus)) {
+    let status = match STATUS.write() {
+        Ok(mut status) => {
+            update(&mut status);
+            status.clone()
+        }
+        Err(_) => return,
+    };
+    let _ = app.emit("rclone-server-status", status);
+}
+
+/// Record a state change of the RC server
+pub fn set_state<R: Runtime>(app: &AppHandle<R>, state: ServerState) {
+    update_status(app, |status| {
+        status.state = state;
+        if state == ServerState::Running {
+            status.restart_attempt = None;
+        }
+    });
+}
+
+/// Restart the server, boxed so the supervisor's future type does not
+/// contain itself through `start_rc_server`
+fn restart<R: Runtime>(
+    app: AppHandle<R>,
+) -> Pin<Box<dyn Future<Output = Result<(), AppError>> + Send>> {
+    Box::pin(async move { server::get_sdk_client(&app).await.map(|_| ()) })
+}
+
+/// Watch the events of a server process and restart it if it exits
+/// without being asked to
+pub fn spawn_supervisor<R: Runtime>(app: &AppHandle<R>, mut rx: Receiver<CommandEvent>, pid: u32) {
+    let app = app.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut stderr = VecDeque::new();
+        let mut exit_code = None;
+        let mut signal = None;
+
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stderr(bytes) => {
+                    let line = String::from_utf8_lossy(&bytes).trim_end().to_string();
+                    if stderr.len() == MAX_STDERR_LINES {
+                        stderr.pop_front();
+                    }
+                    stderr.push_back(line);
+                }
+                CommandEvent::Terminated(payload) => {
+                    exit_code = payload.code;
+                    signal = payload.signal;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        app.state::<crate::SidecarManager>().remove(pid);
+
+        // Stopped on request, or replaced by another server process
+        let is_current = server::session_pid() == Some(pid);
+        if !is_current || server_state() == ServerState::Stopped {
+            return;
+        }
+
+        update_status(&app, |status| {
+            status.state = ServerState::Crashed;
+            status.exit_code = exit_code;
+            status.signal = signal;
+            status.stderr = stderr.into_iter().collect();
+        });
+
+        let mut delay = INITIAL_RESTART_DELAY;
+        for attempt in 1..=MAX_RESTART_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+
+            // Someone else restarted or stopped the server meanwhile
+            if server_state() != ServerState::Crashed && server_state() != ServerState::Restarting {
+                return;
+            }
+
+            update_status(&app, |status| {
+                status.state = ServerState::Restarting;
+                status.restart_attempt = Some(attempt);
+            });
+
+            if restart(app.clone()).await.is_ok() {
+                return;
+            }
+            delay *= 2;
+        }
+
+        set_state(&app, ServerState::Failed);
+    });
+}
+
+#[tauri::command]
+pub fn get_rclone_server_status() -> ServerStatus {
+    STATUS
+        .read()
+        .map(|status| status.clone())
+        .unwrap_or_default()
+}
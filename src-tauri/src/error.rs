@@ -0,0 +1,305 @@
+use rclone_sdk::types::RcError;
+use regex::Regex;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::sync::LazyLock;
+
+/// Error returned by every Tauri command.
+///
+/// Serialized as `{ code, message }`. The code is stable and meant for the
+/// frontend to pick a localized message, the message is English detail.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum AppError {
+    /// The rclone binary or its RC server can't be reached
+    #[error("rclone is unavailable: {0}")]
+    RcloneUnavailable(String),
+    /// No remote configuration exists yet
+    #[error("Remote configuration is required. Please authorize first.")]
+    AuthRequired,
+    #[error("Google Drive authorization expired: {0}")]
+    TokenExpired(String),
+    #[error("Google Drive quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error("Not enough disk space: {0}")]
+    DiskFull(String),
+    #[error("{0}")]
+    Cancelled(String),
+    /// The RC server exited unexpectedly while a job was running
+    #[error("Download interrupted: rclone stopped unexpectedly")]
+    Interrupted,
+    #[error("Invalid source: {0}")]
+    InvalidSource(String),
+    #[error("Invalid destination: {0}")]
+    InvalidDestination(String),
+    /// The request doesn't apply to the current state, e.g. pausing a
+    /// finished job
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Io(String),
+    /// Any other error reported by rclone
+    #[error("rclone error: {0}")]
+    Rclone(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// Stable identifier of the error kind
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::RcloneUnavailable(_) => "rclone_unavailable",
+            Self::AuthRequired => "auth_required",
+            Self::TokenExpired(_) => "token_expired",
+            Self::QuotaExceeded(_) => "quota_exceeded",
+            Self::DiskFull(_) => "disk_full",
+            Self::Cancelled(_) => "cancelled",
+            Self::Interrupted => "interrupted",
+            Self::InvalidSource(_) => "invalid_source",
+            Self::InvalidDestination(_) => "invalid_destination",
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::NotFound(_) => "not_found",
+            Self::Io(_) => "io",
+            Self::Rclone(_) => "rclone",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// Classify an error message reported by rclone, either in an RC
+    /// response or in its log. Google API errors are classified by their
+    /// HTTP code and reason. rclone reports everything else, like OAuth or
+    /// local disk errors, only as text, so those fall back to matching
+    /// known phrases.
+    pub fn from_rclone_message(message: impl Into<String>) -> Self {
+        let message = message.into();
+        if let Some(error) = Self::from_google_api_message(&message) {
+            return error;
+        }
+
+        let lower = message.to_lowercase();
+        if lower.contains("quota") || lower.contains("ratelimitexceeded") {
+            Self::QuotaExceeded(message)
+        } else if lower.contains("invalid_grant")
+            || lower.contains("token expired")
+            || lower.contains("couldn't fetch token")
+        {
+            Self::TokenExpired(message)
+        } else if lower.contains("no space left") || lower.contains("not enough space") {
+            Self::DiskFull(message)
+        } else if lower.contains("context canceled") {
+            Self::Cancelled(message)
+        } else if lower.contains("not found") {
+            Self::NotFound(message)
+        } else {
+            Self::Rclone(message)
+        }
+    }
+
+    /// Classify a Google API error passed on by rclone, formatted as
+    /// `googleapi: Error <code>: <message>, <reason>`
+    fn from_google_api_message(message: &str) -> Option<Self> {
+        static GOOGLE_API_RE: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"googleapi: Error (\d{3})(?:: [^\n]*, (\w+))?").unwrap());
+
+        let caps = GOOGLE_API_RE.captures(message)?;
+        let code: u16 = caps[1].parse().ok()?;
+        let reason = caps.get(2).map_or("", |m| m.as_str());
+        let message = message.to_string();
+        match code {
+            401 => Some(Self::TokenExpired(message)),
+            429 => Some(Self::QuotaExceeded(message)),
+            403 if reason.ends_with("RateLimitExceeded")
+                || reason.ends_with("QuotaExceeded")
+                || reason == "rateLimitExceeded"
+                || reason == "quotaExceeded" =>
+            {
+                Some(Self::QuotaExceeded(message))
+            }
+            404 => Some(Self::NotFound(message)),
+            _ => Some(Self::Rclone(message)),
+        }
+    }
+
+    /// Classify an error returned by the RC API. Its status code tells
+    /// missing files and invalid parameters apart, rclone reports any
+    /// other failure with status 500 and only its message says more.
+    pub fn from_rc_error(error: RcError) -> Self {
+        match error.status {
+            400 => Self::InvalidRequest(error.error),
+            404 => Self::NotFound(error.error),
+            _ => Self::from_rclone_message(error.error),
+        }
+    }
+
+    /// Classify the body of a failed RC response, using the HTTP status
+    /// when the body isn't an RC error
+    pub fn from_rc_body(status: u16, body: &str) -> Self {
+        match serde_json::from_str::<RcError>(body) {
+            Ok(error) => Self::from_rc_error(error),
+            Err(_) if status == 404 => Self::NotFound(body.to_string()),
+            Err(_) => Self::Rclone(body.to_string()),
+        }
+    }
+
+    /// Wrap a failed request to the RC server with a description of what
+    /// was being done. Only a server that can't be reached is unavailable.
+    pub fn request(context: &str, error: impl Into<rclone_sdk::Error<RcError>>) -> Self {
+        match error.into() {
+            rclone_sdk::Error::CommunicationError(e) if e.is_connect() || e.is_timeout() => {
+                Self::RcloneUnavailable(format!("{}: {}", context, e))
+            }
+            e => Self::Rclone(format!("{}: {}", context, e)),
+        }
+    }
+
+    /// Wrap an I/O error with a description of what was being done
+    pub fn io(context: &str, error: std::io::Error) -> Self {
+        let message = format!("{}: {}", context, error);
+        match error.kind() {
+            std::io::ErrorKind::StorageFull => Self::DiskFull(message),
+            std::io::ErrorKind::NotFound => Self::NotFound(message),
+            _ => Self::Io(message),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        Self::io("I/O error", error)
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_connect() || error.is_timeout() {
+            Self::RcloneUnavailable(error.to_string())
+        } else {
+            Self::Internal(error.to_string())
+        }
+    }
+}
+
+impl From<rclone_sdk::Error<RcError>> for AppError {
+    fn from(error: rclone_sdk::Error<RcError>) -> Self {
+        match error {
+            rclone_sdk::Error::CommunicationError(e) if e.is_connect() || e.is_timeout() => {
+                Self::RcloneUnavailable(e.to_string())
+            }
+            rclone_sdk::Error::ErrorResponse(response) => {
+                Self::from_rc_error(response.into_inner())
+            }
+            e => Self::Rclone(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Internal(error.to_string())
+    }
+}
+
+/// Number of background errors kept for a frontend that starts listening late
+const MAX_BACKGROUND_ERRORS: usize = 50;
+
+/// A failure of work no command waits for, like restoring the queue at
+/// startup or pruning backups after a sync
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundError {
+    /// What was being done, e.g. "Failed to prune backups"
+    pub context: String,
+    pub error: AppError,
+    pub time: String,
+}
+
+/// Background errors reported so far. Each one is also emitted as
+/// `background-error`, this keeps those raised before the frontend listened.
+#[derive(Default)]
+pub struct BackgroundErrors(std::sync::Mutex<Vec<BackgroundError>>);
+
+impl BackgroundErrors {
+    pub fn list(&self) -> Vec<BackgroundError> {
+        self.0
+            .lock()
+            .map(|errors| errors.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Report a failure of background work to the frontend
+pub fn report_background_error(app: &tauri::AppHandle, context: &str, error: AppError) {
+    use tauri::{Emitter, Manager};
+
+    let error = BackgroundError {
+        context: context.to_string(),
+        error,
+        time: chrono::Local::now().to_rfc3339(),
+    };
+    if let Some(errors) = app.try_state::<BackgroundErrors>()
+        && let Ok(mut errors) = errors.0.lock()
+    {
+        if errors.len() >= MAX_BACKGROUND_ERRORS {
+            errors.remove(0);
+        }
+        errors.push(error.clone());
+    }
+    let _ = app.emit("background-error", error);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rc_error(status: i64, error: &str) -> RcError {
+        RcError {
+            error: error.to_string(),
+            input: None,
+            path: "operations/list".to_string(),
+            status,
+        }
+    }
+
+    #[test]
+    fn rc_errors_are_classified_by_status_first() {
+        // The message alone would look like a quota error
+        let missing = AppError::from_rc_error(rc_error(404, "directory quota/ not found"));
+        let invalid = AppError::from_rc_error(rc_error(400, "Didn't find key \"fs\" in input"));
+        let generic = AppError::from_rc_error(rc_error(500, "no space left on device"));
+
+        assert_eq!(missing.code(), "not_found");
+        assert_eq!(invalid.code(), "invalid_request");
+        assert_eq!(generic.code(), "disk_full");
+        assert_eq!(
+            AppError::from_rc_body(404, "404 page not found").code(),
+            "not_found"
+        );
+    }
+
+    #[test]
+    fn google_api_errors_are_classified_by_code_and_reason() {
+        let quota = "failed to open source object: googleapi: Error 403: The download quota for this file has been exceeded., downloadQuotaExceeded";
+        let forbidden = "googleapi: Error 403: The user does not have sufficient permissions for this file. Not found in quota list, insufficientFilePermissions";
+        let rate = "googleapi: Error 429: Too many requests, rateLimitExceeded";
+        let auth = "googleapi: Error 401: Invalid Credentials, authError";
+
+        assert_eq!(
+            AppError::from_rclone_message(quota).code(),
+            "quota_exceeded"
+        );
+        // Not classified by the word "quota" in its text
+        assert_eq!(AppError::from_rclone_message(forbidden).code(), "rclone");
+        assert_eq!(AppError::from_rclone_message(rate).code(), "quota_exceeded");
+        assert_eq!(AppError::from_rclone_message(auth).code(), "token_expired");
+    }
+}
@@ -1,4 +1,5 @@
 pub mod api;
+pub mod error;
 pub mod utils;
 
 use std::sync::{Arc, Mutex};
@@ -16,6 +17,18 @@ impl SharedChild {
             }
         }
     }
+
+    fn pid(&self) -> Option<u32> {
+        self.0.lock().ok()?.as_ref().map(|child| child.pid())
+    }
+
+    /// Let go of a process that has exited, so its pid is never killed
+    /// after the system reused it
+    fn forget(&self) {
+        if let Ok(mut lock) = self.0.lock() {
+            lock.take();
+        }
+    }
 }
 
 pub struct SidecarManager {
@@ -38,6 +51,19 @@ impl SidecarManager {
         }
         shared
     }
+
+    /// Drop a sidecar that has exited
+    pub fn remove(&self, pid: u32) {
+        if let Ok(mut lock) = self.processes.lock() {
+            lock.retain(|child| {
+                if child.pid() == Some(pid) {
+                    child.forget();
+                    return false;
+                }
+                true
+            });
+        }
+    }
 }
 
 #[tauri::command]
@@ -45,6 +71,60 @@ async fn check_rclone(app: tauri::AppHandle) -> bool {
     crate::api::rclone::get_rclone_command(&app).is_ok()
 }
 
+/// Failures of background work reported so far, also sent as
+/// `background-error` events as they happen
+#[tauri::command]
+fn list_background_errors(
+    state: tauri::State<'_, error::BackgroundErrors>,
+) -> Vec<error::BackgroundError> {
+    state.list()
+}
+
+/// Bring the main window back, e.g. after it was hidden to the tray
+#[cfg(desktop)]
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Add a tray icon, so the app can keep running scheduled syncs while its
+/// window is closed
+#[cfg(desktop)]
+fn setup_tray(app: &tauri::App) -> tauri::Result<()> {
+    use tauri::menu::{Menu, MenuItem};
+    use tauri::tray::{MouseButton, TrayIconBuilder, TrayIconEvent};
+
+    let show = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&show, &quit])?;
+
+    let mut tray = TrayIconBuilder::new()
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "show" => show_main_window(app),
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::DoubleClick {
+                button: MouseButton::Left,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     #[allow(unused_mut)]
@@ -52,10 +132,7 @@ pub fn run() {
     #[cfg(desktop)]
     {
         builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            let _ = app
-                .get_webview_window("main")
-                .expect("no main window")
-                .set_focus();
+            show_main_window(app);
         }));
     }
     builder
@@ -66,22 +143,96 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .manage(SidecarManager::default())
         .manage(api::gdrive::GdriveAuthState::default())
+        .manage(api::gdrive::CancelledJobs::default())
+        .manage(api::gdrive::Scheduler::default())
+        .manage(error::BackgroundErrors::default())
+        .setup(|app| {
+            #[cfg(desktop)]
+            setup_tray(app)?;
+
+            let queue = api::gdrive::JobQueue::restore(app.handle()).unwrap_or_else(|e| {
+                error::report_background_error(app.handle(), "Failed to restore transfer queue", e);
+                api::gdrive::JobQueue::default()
+            });
+            app.manage(queue);
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                api::gdrive::JobQueue::start(&handle).await;
+                if let Err(e) = api::gdrive::Scheduler::load(&handle).await {
+                    crate::error::report_background_error(
+                        &handle,
+                        "Failed to restore sync schedules",
+                        e,
+                    );
+                }
+            });
+            Ok(())
+        })
+        .on_window_event(|window, event| match event {
+            // Keep scheduled syncs running in the tray instead of quitting
+            tauri::WindowEvent::CloseRequested { api: close, .. }
+                if cfg!(desktop)
+                    && window.label() == "main"
+                    && window.state::<api::gdrive::Scheduler>().keeps_running() =>
+            {
+                close.prevent_close();
+                let _ = window.hide();
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
             check_rclone,
+            list_background_errors,
             api::gdrive::get_gdrive_remotes,
             api::gdrive::create_gdrive_remote,
             api::gdrive::cancel_gdrive_auth,
             api::gdrive::list_gdrive_files,
+            api::gdrive::refresh_gdrive_files,
+            api::gdrive::list_gdrive_folder,
+            api::gdrive::search_archive,
             api::gdrive::download_gdrive,
             api::gdrive::check_dry_run,
+            api::gdrive::cancel_download,
+            api::gdrive::enqueue_download,
+            api::gdrive::list_queue_jobs,
+            api::gdrive::move_queue_job,
+            api::gdrive::cancel_queue_job,
+            api::gdrive::pause_queue_job,
+            api::gdrive::resume_queue_job,
+            api::gdrive::retry_queue_job,
+            api::gdrive::set_queue_concurrency,
+            api::gdrive::list_backups,
+            api::gdrive::prune_backups,
+            api::gdrive::get_backup_retention,
+            api::gdrive::set_backup_retention,
+            api::gdrive::diff_backup,
+            api::gdrive::restore_backup,
+            api::gdrive::list_sync_profiles,
+            api::gdrive::create_sync_profile,
+            api::gdrive::update_sync_profile,
+            api::gdrive::delete_sync_profile,
+            api::gdrive::run_sync_profile,
+            api::gdrive::list_sync_runs,
+            api::gdrive::get_sync_run,
+            api::gdrive::find_file_history,
+            api::gdrive::set_bandwidth_limit,
+            api::gdrive::verify_archive,
+            api::gdrive::compare_archive,
             api::rclone::get_stats,
             api::rclone::stop_rc_server,
+            api::rclone::get_rclone_server_status,
+            api::rclone::tail_rclone_log,
+            api::rclone::list_rclone_logs,
             api::local::scan_local_files
         ])
         .build(tauri::generate_context!())
         .expect("error building tauri application")
         .run(|app_handle, event| {
             if let tauri::RunEvent::Exit = event {
+                // Forget the RC server first so its exit isn't treated as a crash
+                api::rclone::server::discard_session();
+
                 // Kill all sidecars
                 let state = app_handle.state::<SidecarManager>();
                 if let Ok(processes) = state.processes.lock() {
@@ -0,0 +1,214 @@
+//! Snapshot of the sync requests built for every `DownloadConfig` option.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to rewrite `snapshots/download_config.json`
+//! after an intended change, and review the diff.
+
+use chrono::NaiveDateTime;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use unofficial_neuro_kar_manager_lib::api::gdrive::{
+    DownloadConfig, TransferOptions, build_file_filter,
+};
+use unofficial_neuro_kar_manager_lib::error::AppError;
+
+const SOURCE: &str = "https://drive.google.com/drive/folders/FOLDER_ID?usp=sharing";
+const UNIX_DESTINATION: &str = "/home/user/Music";
+const WINDOWS_DESTINATION: &str = "C:/Users/user/Music";
+
+fn backup_time() -> NaiveDateTime {
+    NaiveDateTime::parse_from_str("2026-01-02 03:04:05", "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn snapshot_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/download_config.json")
+}
+
+fn selections() -> [(&'static str, Option<Vec<String>>); 3] {
+    [
+        ("all", None),
+        ("none", Some(Vec::new())),
+        (
+            "picked",
+            Some(vec!["/Album 1".to_string(), "Singles/song.mp3".to_string()]),
+        ),
+    ]
+}
+
+/// The request for every combination of options, keyed by a readable name
+fn build_all() -> BTreeMap<String, Value> {
+    let mut requests = BTreeMap::new();
+
+    for (platform, destination, windows) in [
+        ("unix", UNIX_DESTINATION, false),
+        ("windows", WINDOWS_DESTINATION, true),
+    ] {
+        for sync_mode in [false, true] {
+            for create_subfolder in [false, true] {
+                for (selection, selected_files) in selections() {
+                    for create_backup in [false, true] {
+                        for delete_excluded in [false, true] {
+                            for track_renames in [false, true] {
+                                let config = DownloadConfig::new(
+                                    SOURCE.to_string(),
+                                    destination.to_string(),
+                                    Some("gdrive".to_string()),
+                                    sync_mode,
+                                    create_subfolder,
+                                    selected_files.clone(),
+                                    create_backup,
+                                    delete_excluded,
+                                    track_renames,
+                                )
+                                .unwrap();
+                                let paths = config
+                                    .build_filesystem_paths_at(backup_time(), windows)
+                                    .unwrap();
+
+                                let name = format!(
+                                    "{} {} subfolder={} selection={} backup={} delete_excluded={} track_renames={}",
+                                    platform,
+                                    if sync_mode { "sync" } else { "copy" },
+                                    create_subfolder,
+                                    selection,
+                                    create_backup,
+                                    delete_excluded,
+                                    track_renames,
+                                );
+                                requests.insert(
+                                    name,
+                                    json!({
+                                        "endpoint": config.endpoint(),
+                                        "paths": paths,
+                                        "body": config.build_request_body(&paths),
+                                    }),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    requests
+}
+
+#[test]
+fn sync_requests_match_snapshot() {
+    let actual = build_all();
+    let path = snapshot_path();
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        let contents = serde_json::to_string_pretty(&actual).unwrap();
+        std::fs::write(&path, contents + "\n").unwrap();
+        return;
+    }
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let expected: BTreeMap<String, Value> = serde_json::from_str(&contents).unwrap();
+
+    let changed: BTreeSet<&String> = actual
+        .keys()
+        .chain(expected.keys())
+        .filter(|name| actual.get(*name) != expected.get(*name))
+        .collect();
+    assert!(
+        changed.is_empty(),
+        "sync requests changed, rerun with UPDATE_SNAPSHOTS=1 if intended: {:#?}",
+        changed
+    );
+}
+
+#[test]
+fn subfolder_is_not_added_twice() {
+    let config = DownloadConfig::new(
+        SOURCE.to_string(),
+        "/home/user/Music/An-Unofficial-Neuro-Karaoke-Archive".to_string(),
+        Some("gdrive".to_string()),
+        false,
+        true,
+        None,
+        false,
+        false,
+        false,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.build_destination_path(),
+        PathBuf::from("/home/user/Music/An-Unofficial-Neuro-Karaoke-Archive")
+    );
+}
+
+#[test]
+fn backup_needs_a_parent_directory() {
+    let config = DownloadConfig::new(
+        SOURCE.to_string(),
+        "/".to_string(),
+        Some("gdrive".to_string()),
+        true,
+        false,
+        None,
+        true,
+        false,
+        false,
+    )
+    .unwrap();
+
+    let result = config.build_filesystem_paths_at(backup_time(), false);
+    assert!(matches!(result, Err(AppError::InvalidDestination(_))));
+}
+
+#[test]
+fn transfer_options_tune_the_request() {
+    let config = DownloadConfig::new(
+        SOURCE.to_string(),
+        UNIX_DESTINATION.to_string(),
+        Some("gdrive".to_string()),
+        false,
+        false,
+        None,
+        false,
+        false,
+        false,
+    )
+    .unwrap()
+    .with_transfer_options(TransferOptions {
+        bw_limit: Some("08:00,512k 23:00,off".to_string()),
+        transfers: Some(8),
+        checkers: Some(16),
+        chunk_size: Some("64M".to_string()),
+    })
+    .unwrap();
+
+    let paths = config
+        .build_filesystem_paths_at(backup_time(), false)
+        .unwrap();
+    let body = config.build_request_body(&paths);
+
+    assert_eq!(
+        paths.src_fs,
+        "gdrive,root_folder_id=FOLDER_ID,chunk_size=64M:"
+    );
+    assert_eq!(body["_config"], json!({ "Transfers": 8, "Checkers": 16 }));
+
+    let invalid = config.with_transfer_options(TransferOptions {
+        bw_limit: Some("fast".to_string()),
+        ..Default::default()
+    });
+    assert!(matches!(invalid, Err(AppError::InvalidRequest(_))));
+}
+
+#[test]
+fn file_filter_includes_each_selection_and_its_contents() {
+    let filter = build_file_filter(&["//Album 1".to_string(), "song.mp3".to_string()]);
+
+    assert_eq!(
+        filter,
+        json!({
+            "IncludeRule": ["/Album 1", "/Album 1/**", "/song.mp3", "/song.mp3/**"]
+        })
+    );
+    assert_eq!(build_file_filter(&[]), json!({}));
+}
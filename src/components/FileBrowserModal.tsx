@@ -1,6 +1,7 @@
 import { t } from "@lingui/core/macro";
 import { Plural, Trans } from "@lingui/react/macro";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import {
   ArrowLeft,
   Check,
@@ -12,7 +13,8 @@ import {
   Search,
   X,
 } from "lucide-react";
-import { useCallback, useEffect, useMemo, useState } from "react";
+import { useCallback, useEffect, useMemo, useRef, useState } from "react";
+import { describeError } from "@/lib/errors";
 import { Button } from "./ui/button";
 import { Card } from "./ui/card";
 import { Checkbox } from "./ui/checkbox";
@@ -25,6 +27,44 @@ interface GdriveFile {
   isDir: boolean;
   size: number;
   mimeType: string;
+  modTime: string | null;
+  createdTime: string | null;
+  hashes: Record<string, string>;
+  id: string | null;
+  shortcutId: string | null;
+  isGoogleDoc: boolean;
+}
+
+interface GdriveListing {
+  files: GdriveFile[];
+  refreshedAt: string;
+  refreshing: boolean;
+}
+
+interface FolderEntry extends GdriveFile {
+  childCount: number | null;
+  totalSize: number | null;
+}
+
+interface FolderPage {
+  path: string;
+  entries: FolderEntry[];
+  total: number;
+  offset: number;
+  refreshedAt: string | null;
+}
+
+interface SearchResults {
+  hits: GdriveFile[];
+  total: number;
+  refreshedAt: string;
+  refreshing: boolean;
+}
+
+interface FilesChanged {
+  source: string;
+  remoteConfig: string;
+  refreshedAt: string;
 }
 
 interface FileBrowserModalProps {
@@ -37,6 +77,14 @@ interface FileBrowserModalProps {
   destination?: string;
 }
 
+/** Number of search results shown at once */
+const SEARCH_LIMIT = 500;
+
+/** Whether a path is inside a folder, at any depth */
+function isInside(path: string, folder: string) {
+  return path.startsWith(`${folder}/`);
+}
+
 export default function FileBrowserModal({
   isOpen,
   onClose,
@@ -48,34 +96,94 @@ export default function FileBrowserModal({
 }: FileBrowserModalProps) {
   const [currentPath, setCurrentPath] = useState("");
   const [history, setHistory] = useState<string[]>([]);
-  const [allItems, setAllItems] = useState<GdriveFile[]>([]);
+  // Entries of the open folder, or the search results while searching
+  const [items, setItems] = useState<GdriveFile[]>([]);
+  const [total, setTotal] = useState(0);
   const [loading, setLoading] = useState(false);
+  const [loadingMore, setLoadingMore] = useState(false);
   const [scanning, setScanning] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  // Only the latest request may update the list
+  const requestRef = useRef(0);
 
-  // Selection is a Set of paths relative to root
+  // Selection is a Set of paths relative to root. A selected folder stands
+  // for everything below it.
   const [selection, setSelection] = useState<Set<string>>(
     new Set(initialSelection),
   );
   const [search, setSearch] = useState("");
   const [debouncedSearch, setDebouncedSearch] = useState("");
 
-  const loadAllFiles = useCallback(async () => {
-    setLoading(true);
-    setError(null);
+  const loadFolder = useCallback(
+    async (path: string) => {
+      const request = ++requestRef.current;
+      setLoading(true);
+      setError(null);
+      try {
+        const page = await invoke<FolderPage>("list_gdrive_folder", {
+          source,
+          remoteConfig,
+          path,
+        });
+        if (request !== requestRef.current) return;
+        setItems(page.entries);
+        setTotal(page.total);
+      } catch (err) {
+        if (request !== requestRef.current) return;
+        console.error(err);
+        setError(`Failed to list files: ${describeError(err)}`);
+      } finally {
+        if (request === requestRef.current) setLoading(false);
+      }
+    },
+    [source, remoteConfig],
+  );
+
+  const searchFiles = useCallback(
+    async (query: string) => {
+      const request = ++requestRef.current;
+      setLoading(true);
+      setError(null);
+      try {
+        const results = await invoke<SearchResults>("search_archive", {
+          source,
+          remoteConfig,
+          query: { query, includeFolders: true, limit: SEARCH_LIMIT },
+        });
+        if (request !== requestRef.current) return;
+        setItems(results.hits);
+        setTotal(results.hits.length);
+      } catch (err) {
+        if (request !== requestRef.current) return;
+        console.error(err);
+        setError(`Failed to list files: ${describeError(err)}`);
+      } finally {
+        if (request === requestRef.current) setLoading(false);
+      }
+    },
+    [source, remoteConfig],
+  );
+
+  const loadMore = async () => {
+    const request = requestRef.current;
+    setLoadingMore(true);
     try {
-      const result = await invoke<GdriveFile[]>("list_gdrive_files", {
+      const page = await invoke<FolderPage>("list_gdrive_folder", {
         source,
         remoteConfig,
+        path: currentPath,
+        offset: items.length,
       });
-      setAllItems(result);
+      if (request !== requestRef.current) return;
+      setItems((prev) => [...prev, ...page.entries]);
+      setTotal(page.total);
     } catch (err) {
       console.error(err);
-      setError(`Failed to list files: ${err}`);
+      setError(`Failed to list files: ${describeError(err)}`);
     } finally {
-      setLoading(false);
+      setLoadingMore(false);
     }
-  }, [source, remoteConfig]);
+  };
 
   useEffect(() => {
     if (isOpen) {
@@ -83,11 +191,53 @@ export default function FileBrowserModal({
       setHistory([]);
       setSearch("");
       setDebouncedSearch("");
-      loadAllFiles();
     }
-  }, [isOpen, loadAllFiles]);
+  }, [isOpen]);
+
+  // List the open folder, or search the whole archive
+  useEffect(() => {
+    if (!isOpen) return;
+    if (debouncedSearch) {
+      searchFiles(debouncedSearch);
+    } else {
+      loadFolder(currentPath);
+    }
+  }, [isOpen, currentPath, debouncedSearch, loadFolder, searchFiles]);
 
-  // Debounce search input (prevent excessive filtering and lag)
+  // Pick up a newer listing once the background refresh finds changes
+  useEffect(() => {
+    if (!isOpen) return;
+
+    const unlistenPromise = listen<FilesChanged>(
+      "gdrive-files-changed",
+      (event) => {
+        if (
+          event.payload.source === source &&
+          event.payload.remoteConfig === remoteConfig
+        ) {
+          if (debouncedSearch) {
+            searchFiles(debouncedSearch);
+          } else {
+            loadFolder(currentPath);
+          }
+        }
+      },
+    );
+
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, [
+    isOpen,
+    source,
+    remoteConfig,
+    currentPath,
+    debouncedSearch,
+    loadFolder,
+    searchFiles,
+  ]);
+
+  // Debounce search input (prevent excessive requests and lag)
   useEffect(() => {
     const timer = setTimeout(() => {
       setDebouncedSearch(search);
@@ -101,95 +251,52 @@ export default function FileBrowserModal({
     setCurrentPath(prev || "");
   };
 
-  // Memoize descendant map for performance
-  const descendantsMap = useMemo(() => {
-    const map = new Map<string, string[]>();
-    allItems.forEach((item) => {
-      if (item.isDir) {
-        const descendants = allItems
-          .filter(
-            (i) => i.path !== item.path && i.path.startsWith(`${item.path}/`),
-          )
-          .map((i) => i.path);
-        map.set(item.path, descendants);
-      }
-    });
-    return map;
-  }, [allItems]);
+  // Whether a path is selected through one of the folders it is in
+  const isInherited = (path: string) => {
+    for (const selected of selection) {
+      if (isInside(path, selected)) return true;
+    }
+    return false;
+  };
+
+  /** Select or unselect a path, dropping the paths below it either way */
+  const setSelected = (next: Set<string>, path: string, selected: boolean) => {
+    for (const other of next) {
+      if (isInside(other, path)) next.delete(other);
+    }
+    selected ? next.add(path) : next.delete(path);
+  };
 
-  const toggleSelection = (path: string, isDir: boolean) => {
+  const toggleSelection = (path: string) => {
     setSelection((prev) => {
       const next = new Set(prev);
-      const isSelected = next.has(path);
-
-      if (isDir) {
-        const descendants = descendantsMap.get(path) || [];
-        if (isSelected) {
-          next.delete(path);
-          descendants.forEach((d) => {
-            next.delete(d);
-          });
-        } else {
-          next.add(path);
-          descendants.forEach((d) => {
-            next.add(d);
-          });
-        }
-      } else {
-        isSelected ? next.delete(path) : next.add(path);
-      }
-
+      setSelected(next, path, !prev.has(path));
       return next;
     });
   };
 
-  const displayItems = useMemo(() => {
-    if (!allItems.length) return [];
-
-    if (debouncedSearch) {
-      const searchLower = debouncedSearch.toLowerCase();
-      return allItems.filter((i) => i.name.toLowerCase().includes(searchLower));
-    }
-
-    const prefix = currentPath ? `${currentPath}/` : "";
-    return allItems.filter((item) => {
-      if (currentPath && !item.path.startsWith(prefix)) return false;
-      const relative = currentPath ? item.path.slice(prefix.length) : item.path;
-      return !relative.includes("/");
-    });
-  }, [allItems, debouncedSearch, currentPath]);
-
   const sortedDisplayItems = useMemo(() => {
-    return [...displayItems].sort((a, b) => {
+    // Search results come ranked, folders come sorted
+    if (debouncedSearch) return items;
+    return [...items].sort((a, b) => {
       if (a.isDir === b.isDir) {
         return a.name.localeCompare(b.name);
       }
       return a.isDir ? -1 : 1;
     });
-  }, [displayItems]);
+  }, [items, debouncedSearch]);
 
   const allVisibleSelected =
     sortedDisplayItems.length > 0 &&
-    sortedDisplayItems.every((i) => selection.has(i.path));
+    sortedDisplayItems.every(
+      (i) => selection.has(i.path) || isInherited(i.path),
+    );
 
   const handleSelectAll = (checked: boolean) => {
     setSelection((prev) => {
       const next = new Set(prev);
       sortedDisplayItems.forEach((item) => {
-        const descendants = item.isDir
-          ? descendantsMap.get(item.path) || []
-          : [];
-        if (checked) {
-          next.add(item.path);
-          descendants.forEach((d) => {
-            next.add(d);
-          });
-        } else {
-          next.delete(item.path);
-          descendants.forEach((d) => {
-            next.delete(d);
-          });
-        }
+        setSelected(next, item.path, checked);
       });
       return next;
     });
@@ -199,15 +306,16 @@ export default function FileBrowserModal({
     if (!destination) return;
     setScanning(true);
     try {
-      const existingFiles = await invoke<string[]>("scan_local_files", {
-        path: destination,
-      });
+      const [existingFiles, listing] = await Promise.all([
+        invoke<string[]>("scan_local_files", { path: destination }),
+        invoke<GdriveListing>("list_gdrive_files", { source, remoteConfig }),
+      ]);
 
       const existingSet = new Set(existingFiles);
 
       setSelection((prev) => {
         const next = new Set(prev);
-        allItems.forEach((item) => {
+        listing.files.forEach((item) => {
           if (!item.isDir && existingSet.has(item.path)) {
             next.add(item.path);
           }
@@ -304,10 +412,11 @@ export default function FileBrowserModal({
                     className="flex items-center gap-3 p-2 hover:bg-accent rounded-md group transition-colors"
                   >
                     <Checkbox
-                      checked={selection.has(item.path)}
-                      onCheckedChange={() =>
-                        toggleSelection(item.path, item.isDir)
+                      checked={
+                        selection.has(item.path) || isInherited(item.path)
                       }
+                      disabled={isInherited(item.path)}
+                      onCheckedChange={() => toggleSelection(item.path)}
                       className="mr-2"
                     />
 
@@ -319,8 +428,9 @@ export default function FileBrowserModal({
                           setHistory((prev) => [...prev, currentPath]);
                           setCurrentPath(item.path);
                           setSearch("");
-                        } else {
-                          toggleSelection(item.path, false);
+                          setDebouncedSearch("");
+                        } else if (!isInherited(item.path)) {
+                          toggleSelection(item.path);
                         }
                       }}
                     >
@@ -353,6 +463,21 @@ export default function FileBrowserModal({
                   </div>
                 ))
               )}
+              {!debouncedSearch && items.length < total && (
+                <div className="flex justify-center pt-2">
+                  <Button
+                    variant="outline"
+                    size="sm"
+                    onClick={loadMore}
+                    disabled={loadingMore}
+                  >
+                    {loadingMore && (
+                      <Loader2 className="mr-2 h-4 w-4 animate-spin" />
+                    )}
+                    <Trans>Load more</Trans>
+                  </Button>
+                </div>
+              )}
             </div>
           )}
         </ScrollArea>
@@ -2,6 +2,7 @@ import { Download, Home, Menu, X } from "lucide-react";
 import { useState } from "react";
 import { Link, useLocation } from "react-router-dom";
 import { Button } from "@/components/ui/button";
+import { useBackgroundErrors } from "@/hooks/useBackgroundErrors";
 import { cn } from "@/lib/utils";
 import DownloadPage from "@/pages/Download";
 import HomePage from "@/pages/HomePage";
@@ -9,6 +10,7 @@ import HomePage from "@/pages/HomePage";
 export default function Layout() {
   const location = useLocation();
   const [isSidebarOpen, setIsSidebarOpen] = useState(false);
+  useBackgroundErrors();
 
   return (
     <div className="flex h-screen w-full bg-background text-foreground overflow-hidden pt-[env(safe-area-inset-top)] pl-[env(safe-area-inset-left)] pr-[env(safe-area-inset-right)] pb-[env(safe-area-inset-bottom)]">
@@ -0,0 +1,33 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect } from "react";
+import { type AppError, describeError } from "@/lib/errors";
+
+/** A failure of work the backend does on its own, like pruning backups */
+export interface BackgroundError {
+  context: string;
+  error: AppError;
+  time: string;
+}
+
+function reportError({ context, error }: BackgroundError) {
+  console.error(`${context}: ${describeError(error)}`);
+}
+
+/** Log failures of background work, including those from before startup */
+export function useBackgroundErrors() {
+  useEffect(() => {
+    invoke<BackgroundError[]>("list_background_errors")
+      .then((errors) => errors.forEach(reportError))
+      .catch(console.error);
+
+    const unlistenPromise = listen<BackgroundError>(
+      "background-error",
+      (event) => reportError(event.payload),
+    );
+
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+}
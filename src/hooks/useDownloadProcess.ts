@@ -1,11 +1,27 @@
+import { plural, t } from "@lingui/core/macro";
 import { invoke } from "@tauri-apps/api/core";
-import { useRef, useState } from "react";
-import type { DownloadParams, DryRunResult } from "@/types/download";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useRef, useState } from "react";
+import { describeError, isCancelledError } from "@/lib/errors";
+import type {
+  DownloadParams,
+  DownloadSummary,
+  DryRunResult,
+} from "@/types/download";
+
+/** Stop a single rclone job, leaving other transfers running */
+function cancelJob(jobid: number) {
+  return invoke("cancel_download", { jobid });
+}
 
 export function useDownloadProcess() {
   const [loading, setLoading] = useState(false);
   const [cancelling, setCancelling] = useState(false);
   const isCancelledRef = useRef(false);
+  // Whether a dry run or download was requested and hasn't returned yet
+  const isRunningRef = useRef(false);
+  // rclone job id of the running dry run or download, once it started
+  const jobIdRef = useRef<number | null>(null);
   const [status, setStatus] = useState("");
   const [log, setLog] = useState("");
   const [dryRunResult, setDryRunResult] = useState<DryRunResult | null>(null);
@@ -17,6 +33,21 @@ export function useDownloadProcess() {
     setLog((prev) => `${prev}${message}\n`);
   };
 
+  useEffect(() => {
+    const unlistenPromise = listen<number>("download-job-started", (event) => {
+      if (!isRunningRef.current) return;
+      jobIdRef.current = event.payload;
+      // Cancel was pressed before the job was submitted
+      if (isCancelledRef.current) {
+        cancelJob(event.payload).catch(console.error);
+      }
+    });
+
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
   const setCancelledState = (isCancelled: boolean) => {
     isCancelledRef.current = isCancelled;
     setCancelling(isCancelled);
@@ -37,8 +68,7 @@ export function useDownloadProcess() {
   };
 
   const isCancellationError = (error: unknown) => {
-    const errStr = String(error);
-    return errStr.includes("cancelled") || isCancelledRef.current;
+    return isCancelledError(error) || isCancelledRef.current;
   };
 
   const cancelDownload = async () => {
@@ -56,12 +86,15 @@ export function useDownloadProcess() {
     // Set cancellation flags
     setCancelledState(true);
 
+    appendLog("\nRequesting cancellation...");
+    // Without a job id yet, the job is stopped as soon as it starts
+    if (jobIdRef.current === null) return;
+
     try {
-      appendLog("\nRequesting cancellation...");
-      await invoke("stop_rc_server");
+      await cancelJob(jobIdRef.current);
     } catch (err) {
-      console.error("Failed to stop rclone", err);
-      appendLog(`\nFailed to stop rclone: ${err}`);
+      console.error("Failed to cancel download", err);
+      appendLog(`\nFailed to cancel download: ${describeError(err)}`);
       // Even if stop fails, consider it cancelled on frontend naturally
       setCancelling(false);
     }
@@ -79,8 +112,9 @@ export function useDownloadProcess() {
 
     clearPendingState();
 
+    isRunningRef.current = true;
     try {
-      const output = await invoke<string>("download_gdrive", {
+      const summary = await invoke<DownloadSummary>("download_gdrive", {
         source: params.source,
         destination: params.destination,
         remoteConfig: params.remoteConfig,
@@ -91,17 +125,37 @@ export function useDownloadProcess() {
         deleteExcluded: params.deleteExcluded,
         trackRenames: params.trackRenames,
       });
-      setStatus("Download completed successfully.");
-      appendLog(`\n${output}`);
+      const { errors, transfers, bytes, checks, deletes, renames } = summary;
+      const seconds = summary.elapsedTime.toFixed(1);
+      setStatus(
+        summary.status === "partiallyFailed"
+          ? plural(errors, {
+              one: "Download finished with # error.",
+              other: "Download finished with # errors.",
+            })
+          : t`Download completed successfully.`,
+      );
+      const totals = t`Transferred ${plural(transfers, {
+        one: "# file",
+        other: "# files",
+      })} (${bytes} bytes), checked ${checks}, deleted ${deletes}, renamed ${renames} in ${seconds}s.`;
+      appendLog(`\n${totals}`);
+      for (const failed of summary.failedFiles) {
+        const path = failed.path ?? t`(unknown file)`;
+        const reason = failed.reason;
+        appendLog(t`Failed: ${path}: ${reason}`);
+      }
     } catch (error) {
       if (isCancellationError(error)) {
         handleTransferCancelled();
       } else {
         console.error(error);
         setStatus("Download failed.");
-        appendLog(`\nError: ${error}`);
+        appendLog(`\nError: ${describeError(error)}`);
       }
     } finally {
+      isRunningRef.current = false;
+      jobIdRef.current = null;
       setLoading(false);
       setCancelledState(false);
     }
@@ -141,6 +195,7 @@ export function useDownloadProcess() {
 
     appendLog("\nPerforming dry run to check for potential file deletions...");
 
+    isRunningRef.current = true;
     try {
       const result = await invoke<DryRunResult>("check_dry_run", {
         source: params.source,
@@ -151,6 +206,8 @@ export function useDownloadProcess() {
         selectedFiles: params.selectedFiles,
         deleteExcluded: params.deleteExcluded,
       });
+      isRunningRef.current = false;
+      jobIdRef.current = null;
 
       // If user clicked cancel while dry run was in progress, abort here
       if (isCancelledRef.current) {
@@ -172,12 +229,14 @@ export function useDownloadProcess() {
         await runDownload(params);
       }
     } catch (error) {
+      isRunningRef.current = false;
+      jobIdRef.current = null;
       if (isCancellationError(error)) {
         handleTransferCancelled("\nDry run cancelled.");
         return;
       }
 
-      appendLog(`\nDry run failed: ${error}`);
+      appendLog(`\nDry run failed: ${describeError(error)}`);
       appendLog("You can still proceed, but file deletion status is unknown.");
 
       setDryRunResult({
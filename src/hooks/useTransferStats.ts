@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useEffect, useState } from "react";
 
 export interface CoreStatsTransfer {
@@ -7,7 +7,6 @@ export interface CoreStatsTransfer {
   bytes: number;
   percentage: number;
   speed: number;
-  speedAvg: number;
   eta?: number;
 }
 
@@ -19,20 +18,55 @@ export interface CoreStatsResponse {
   transferring: CoreStatsTransfer[];
 }
 
+/** Progress of a single rclone job, as sent by its job monitor */
+interface TransferProgress extends CoreStatsResponse {
+  jobid: number;
+}
+
+/** Add up the progress of every running job */
+function combine(jobs: Map<number, TransferProgress>): CoreStatsResponse {
+  const stats: CoreStatsResponse = {
+    bytes: 0,
+    totalBytes: 0,
+    speed: 0,
+    transfers: 0,
+    transferring: [],
+  };
+  for (const job of jobs.values()) {
+    stats.bytes += job.bytes;
+    stats.totalBytes += job.totalBytes;
+    stats.speed += job.speed;
+    stats.transfers += job.transfers;
+    stats.transferring.push(...job.transferring);
+  }
+  return stats;
+}
+
 export function useTransferStats() {
   const [stats, setStats] = useState<CoreStatsResponse | null>(null);
 
   useEffect(() => {
-    const interval = setInterval(async () => {
-      try {
-        const res = await invoke<CoreStatsResponse>("get_stats");
-        setStats(res);
-      } catch (_) {
-        // Silent fail on stats fetch (maybe rclone not running yet)
-      }
-    }, 100);
-
-    return () => clearInterval(interval);
+    const jobs = new Map<number, TransferProgress>();
+
+    const unlistenProgress = listen<TransferProgress>(
+      "transfer-progress",
+      (event) => {
+        jobs.set(event.payload.jobid, event.payload);
+        setStats(combine(jobs));
+      },
+    );
+    const unlistenFinished = listen<TransferProgress>(
+      "transfer-finished",
+      (event) => {
+        jobs.delete(event.payload.jobid);
+        setStats(combine(jobs));
+      },
+    );
+
+    return () => {
+      unlistenProgress.then((unlisten) => unlisten());
+      unlistenFinished.then((unlisten) => unlisten());
+    };
   }, []);
 
   return stats;
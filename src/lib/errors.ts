@@ -0,0 +1,62 @@
+import type { MessageDescriptor } from "@lingui/core";
+import { msg } from "@lingui/core/macro";
+import i18n from "@/i18n";
+
+export type AppErrorCode =
+  | "rclone_unavailable"
+  | "auth_required"
+  | "token_expired"
+  | "quota_exceeded"
+  | "disk_full"
+  | "cancelled"
+  | "interrupted"
+  | "invalid_source"
+  | "invalid_destination"
+  | "invalid_request"
+  | "not_found"
+  | "io"
+  | "rclone"
+  | "internal";
+
+/** Error returned by a Tauri command */
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+}
+
+const errorMessages: Partial<Record<AppErrorCode, MessageDescriptor>> = {
+  rclone_unavailable: msg`Rclone is not available. Try restarting the app.`,
+  auth_required: msg`Please authorize Google Drive first.`,
+  token_expired:
+    msg`Your Google Drive authorization has expired. Please authorize again.`,
+  quota_exceeded:
+    msg`The Google Drive download quota has been exceeded. Try again later.`,
+  disk_full: msg`There is not enough disk space at the destination.`,
+  interrupted: msg`Rclone stopped unexpectedly during the transfer.`,
+  invalid_source:
+    msg`The Google Drive folder could not be found. Check the link or ID.`,
+  invalid_destination: msg`The destination folder is not valid.`,
+};
+
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    "code" in error &&
+    "message" in error
+  );
+}
+
+export function isCancelledError(error: unknown): boolean {
+  return isAppError(error) && error.code === "cancelled";
+}
+
+/** Describe a command error, localized when its code is known */
+export function describeError(error: unknown): string {
+  if (!isAppError(error)) {
+    return String(error);
+  }
+
+  const localized = errorMessages[error.code];
+  return localized ? `${i18n._(localized)} (${error.message})` : error.message;
+}
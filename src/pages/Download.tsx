@@ -18,6 +18,7 @@ import { useAppConfig } from "@/hooks/useAppConfig";
 import { useDownloadForm } from "@/hooks/useDownloadForm";
 import { useDownloadProcess } from "@/hooks/useDownloadProcess";
 import { useRemoteConfig } from "@/hooks/useRemoteConfig";
+import { describeError } from "@/lib/errors";
 
 export default function DownloadPage() {
   const { config, loading: configLoading, saveConfig } = useAppConfig();
@@ -116,7 +117,7 @@ export default function DownloadPage() {
       );
       setShowAuthDialog(false);
     } catch (err) {
-      download.appendLog(`\nAuthorization failed: ${err}`);
+      download.appendLog(`\nAuthorization failed: ${describeError(err)}`);
       setShowAuthDialog(false);
     }
   };
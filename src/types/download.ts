@@ -15,3 +15,20 @@ export interface DownloadParams {
   deleteExcluded: boolean;
   trackRenames: boolean;
 }
+
+export interface FailedFile {
+  path: string | null;
+  reason: string;
+}
+
+export interface DownloadSummary {
+  status: "completed" | "partiallyFailed";
+  transfers: number;
+  checks: number;
+  deletes: number;
+  renames: number;
+  bytes: number;
+  elapsedTime: number;
+  errors: number;
+  failedFiles: FailedFile[];
+}